@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::checks::Check;
+use crate::checks::CheckOutcome;
+use crate::checks::CheckStatus;
+use crate::git;
+
+/// Older entries are dropped once the cache holds this many passes.
+const MAX_ENTRIES: usize = 200;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+  tree: String,
+  command: String,
+  secs: f64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Entries {
+  #[serde(default)]
+  passed: Vec<Entry>,
+}
+
+/// Passing check results in `.git/mrt/check-cache.toml`, keyed on the check command and the
+/// tree hash of the working tree content it ran against.
+pub struct CheckCache {
+  path: PathBuf,
+  tree: String,
+  entries: Entries,
+}
+
+impl CheckCache {
+  pub fn load() -> Result<Self> {
+    let path = git::mrt_dir()?.join("check-cache.toml");
+    Ok(Self::open(path, git::working_tree_hash()?))
+  }
+
+  /// The cache at `path`, answering for the content hashed as `tree`.
+  fn open(path: PathBuf, tree: String) -> Self {
+    let entries = match fs::read_to_string(&path) {
+      Ok(contents) => toml::from_str(&contents).unwrap_or_default(),
+      Err(_) => Entries::default(),
+    };
+
+    CheckCache { path, tree, entries }
+  }
+
+  /// A passing outcome for `check` if it already passed against this exact content.
+  pub fn lookup(&self, check: &Check) -> Option<CheckOutcome> {
+    let entry =
+      self.entries.passed.iter().find(|e| e.tree == self.tree && e.command == check.command)?;
+
+    Some(CheckOutcome {
+      name: check.name.clone(),
+      status: CheckStatus::Passed,
+      duration: Duration::from_secs_f64(entry.secs),
+      stdout: String::new(),
+      stderr: String::new(),
+    })
+  }
+
+  /// Remember the checks that passed and write the cache back to disk.
+  pub fn record(&mut self, checks: &[&Check], outcomes: &[CheckOutcome]) -> Result<()> {
+    for outcome in outcomes.iter().filter(|o| o.passed()) {
+      let Some(check) = checks.iter().find(|c| c.name == outcome.name) else {
+        continue;
+      };
+      if self.lookup(check).is_some() {
+        continue;
+      }
+
+      self.entries.passed.push(Entry {
+        tree: self.tree.clone(),
+        command: check.command.clone(),
+        secs: outcome.duration.as_secs_f64(),
+      });
+    }
+
+    let excess = self.entries.passed.len().saturating_sub(MAX_ENTRIES);
+    self.entries.passed.drain(..excess);
+
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string(&self.entries).context("failed to serialize check cache")?;
+    fs::write(&self.path, contents)
+      .with_context(|| format!("failed to write {}", self.path.display()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(name: &str, command: &str) -> Check {
+    Check {
+      name: name.to_string(),
+      command: command.to_string(),
+      timeout: None,
+      parallel: false,
+      group: None,
+      paths: Vec::new(),
+      branches: None,
+      fix: None,
+    }
+  }
+
+  fn outcome(name: &str, status: CheckStatus) -> CheckOutcome {
+    CheckOutcome {
+      name: name.to_string(),
+      status,
+      duration: Duration::from_secs(2),
+      stdout: String::new(),
+      stderr: String::new(),
+    }
+  }
+
+  #[test]
+  fn lookup_matches_on_command_and_tree() {
+    let dir = std::env::temp_dir().join(format!("mrt-check-cache-{}", std::process::id()));
+    let path = dir.join("check-cache.toml");
+    let _ = fs::remove_dir_all(&dir);
+
+    let test = check("test", "cargo test");
+    let lint = check("lint", "cargo clippy");
+    let mut cache = CheckCache::open(path.clone(), "tree-a".to_string());
+    cache
+      .record(
+        &[&test, &lint],
+        &[outcome("test", CheckStatus::Passed), outcome("lint", CheckStatus::Failed(Some(1)))],
+      )
+      .unwrap();
+
+    let cached = cache.lookup(&test).unwrap();
+    assert!(cached.passed());
+    assert_eq!(cached.duration, Duration::from_secs(2));
+    assert!(cache.lookup(&lint).is_none());
+
+    // The same command under another name still counts; another command doesn't.
+    assert!(cache.lookup(&check("tests", "cargo test")).is_some());
+    assert!(cache.lookup(&check("test", "cargo test --all")).is_none());
+
+    let reloaded = CheckCache::open(path.clone(), "tree-a".to_string());
+    assert!(reloaded.lookup(&test).is_some());
+    let changed = CheckCache::open(path, "tree-b".to_string());
+    assert!(changed.lookup(&test).is_none());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn record_keeps_only_the_newest_entries() {
+    let dir = std::env::temp_dir().join(format!("mrt-check-cache-cap-{}", std::process::id()));
+    let path = dir.join("check-cache.toml");
+    let _ = fs::remove_dir_all(&dir);
+
+    let test = check("test", "cargo test");
+    for i in 0..=MAX_ENTRIES {
+      let mut cache = CheckCache::open(path.clone(), format!("tree-{i}"));
+      cache.record(&[&test], &[outcome("test", CheckStatus::Passed)]).unwrap();
+    }
+
+    let cache = CheckCache::open(path.clone(), "tree-0".to_string());
+    assert_eq!(cache.entries.passed.len(), MAX_ENTRIES);
+    assert!(cache.lookup(&test).is_none());
+    let newest = CheckCache::open(path, format!("tree-{MAX_ENTRIES}"));
+    assert!(newest.lookup(&test).is_some());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
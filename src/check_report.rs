@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde_json::json;
+
+use crate::checks::Check;
+use crate::checks::CheckOutcome;
+use crate::checks::CheckStatus;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ReportFormat {
+  Json,
+  Junit,
+}
+
+impl ReportFormat {
+  /// JUnit for `.xml` paths, JSON for anything else.
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension().and_then(|e| e.to_str()) {
+      Some("xml") => ReportFormat::Junit,
+      _ => ReportFormat::Json,
+    }
+  }
+}
+
+/// Write the outcome of each check that ran, with its command, exit code, duration and output.
+pub fn write(
+  path: &Path, format: ReportFormat, checks: &[Check], outcomes: &[CheckOutcome],
+) -> Result<()> {
+  let command = |name: &str| {
+    checks.iter().find(|c| c.name == name).map(|c| c.command.as_str()).unwrap_or_default()
+  };
+
+  let contents = match format {
+    ReportFormat::Json => json_report(outcomes, command),
+    ReportFormat::Junit => junit_report(outcomes, command),
+  };
+
+  fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+  println!("wrote check report to {}", path.display());
+  Ok(())
+}
+
+fn json_report<'a>(outcomes: &[CheckOutcome], command: impl Fn(&str) -> &'a str) -> String {
+  let checks: Vec<_> = outcomes
+    .iter()
+    .map(|o| {
+      json!({
+        "name": o.name,
+        "command": command(&o.name),
+        "passed": o.passed(),
+        "exit_code": exit_code(&o.status),
+        "timed_out": matches!(o.status, CheckStatus::TimedOut(_)),
+        "duration_secs": o.duration.as_secs_f64(),
+        "stdout": o.stdout,
+        "stderr": o.stderr,
+      })
+    })
+    .collect();
+
+  let report = json!({ "passed": outcomes.iter().all(CheckOutcome::passed), "checks": checks });
+  format!("{report:#}\n")
+}
+
+fn junit_report<'a>(outcomes: &[CheckOutcome], command: impl Fn(&str) -> &'a str) -> String {
+  let failures = outcomes.iter().filter(|o| !o.passed()).count();
+  let total: f64 = outcomes.iter().map(|o| o.duration.as_secs_f64()).sum();
+
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  xml.push_str(&format!(
+    "<testsuite name=\"mrt checks\" tests=\"{}\" failures=\"{failures}\" time=\"{total:.3}\">\n",
+    outcomes.len()
+  ));
+
+  for o in outcomes {
+    xml.push_str(&format!(
+      "  <testcase name=\"{}\" classname=\"mrt.checks\" time=\"{:.3}\">\n",
+      escape(&o.name),
+      o.duration.as_secs_f64()
+    ));
+
+    let message = match o.status {
+      CheckStatus::Passed => None,
+      CheckStatus::Failed(Some(code)) => Some(format!("exit code {code}")),
+      CheckStatus::Failed(None) => Some("killed by signal".to_string()),
+      CheckStatus::TimedOut(secs) => Some(format!("timed out after {secs}s")),
+    };
+    if let Some(message) = message {
+      xml.push_str(&format!(
+        "    <failure message=\"{}\">{}</failure>\n",
+        escape(&message),
+        escape(command(&o.name))
+      ));
+    }
+    if !o.stdout.is_empty() {
+      xml.push_str(&format!("    <system-out>{}</system-out>\n", escape(&o.stdout)));
+    }
+    if !o.stderr.is_empty() {
+      xml.push_str(&format!("    <system-err>{}</system-err>\n", escape(&o.stderr)));
+    }
+
+    xml.push_str("  </testcase>\n");
+  }
+
+  xml.push_str("</testsuite>\n");
+  xml
+}
+
+fn exit_code(status: &CheckStatus) -> Option<i32> {
+  match status {
+    CheckStatus::Passed => Some(0),
+    CheckStatus::Failed(code) => *code,
+    CheckStatus::TimedOut(_) => None,
+  }
+}
+
+/// Escape text for XML, dropping ANSI colour codes and the other control characters XML 1.0
+/// doesn't allow, which tools put in their output when they think they're on a terminal.
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  let mut chars = text.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      // A CSI sequence such as `\x1b[1;31m` runs up to its final byte, in `@` to `~`.
+      '\x1b' if chars.peek() == Some(&'[') => {
+        chars.next();
+        for c in chars.by_ref() {
+          if ('@'..='~').contains(&c) {
+            break;
+          }
+        }
+      },
+      '\t' | '\n' | '\r' => escaped.push(c),
+      c if c.is_control() || matches!(c, '\u{fffe}' | '\u{ffff}') => {},
+      c => escaped.push(c),
+    }
+  }
+
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn escape_handles_markup_characters() {
+    assert_eq!(
+      escape("a < b && c > \"d\" 'e'"),
+      "a &lt; b &amp;&amp; c &gt; &quot;d&quot; &apos;e&apos;"
+    );
+  }
+
+  #[test]
+  fn escape_drops_colour_codes_and_illegal_control_characters() {
+    let output = "\x1b[1;31merror\x1b[0m: bad\x07 input\x00\ttab\r\nnext\x1bx";
+    assert_eq!(escape(output), "error: bad input\ttab\r\nnextx");
+  }
+}
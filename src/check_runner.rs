@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::check_cache::CheckCache;
+use crate::check_report;
+use crate::check_report::ReportFormat;
+use crate::checks;
+use crate::checks::Check;
+use crate::checks::CheckOutcome;
+use crate::config;
+use crate::git;
+use crate::utils::confirm;
+
+/// Whether a failing check's `fix` command gets run.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FixMode {
+  Never,
+  Ask,
+  Always,
+}
+
+/// How `mrt ship` and `mrt check` want the checks run.
+pub struct RunOptions<'a> {
+  /// Run exactly these checks, ignoring their conditions. Empty means every check that applies.
+  pub names: &'a [String],
+  /// Paths to match `paths` conditions against, instead of everything changed since the base
+  pub changed: Option<&'a [String]>,
+  pub use_cache: bool,
+  /// Remember passing checks in the cache. Off for dry runs, which shouldn't change anything.
+  pub record: bool,
+  pub stream: bool,
+  pub fix: FixMode,
+  pub report: Option<(&'a Path, ReportFormat)>,
+}
+
+/// Run the checks, write the report file if one was asked for, and fail if any check did.
+///
+/// A failing check with a `fix` command gets its fixer run (after asking, in `FixMode::Ask`),
+/// and then the checks run again against the fixed content. Each fixer runs at most once.
+pub fn run(checks: &[Check], branch: &str, options: &RunOptions) -> Result<Vec<CheckOutcome>> {
+  let mut outcomes = run_selected(checks, branch, options)?;
+  let mut fixed: Vec<&str> = Vec::new();
+
+  loop {
+    let fixable = outcomes.iter().filter(|o| !o.passed()).find_map(|o| {
+      let check = checks.iter().find(|c| c.name == o.name)?;
+      let fix = check.fix.as_deref().filter(|_| !fixed.contains(&check.name.as_str()))?;
+      Some((check, fix))
+    });
+
+    let Some((check, fix)) = fixable.filter(|_| options.fix != FixMode::Never) else {
+      break;
+    };
+    if options.fix == FixMode::Ask
+      && !confirm(&format!("check '{}' failed; run `{fix}`?", check.name))?
+    {
+      break;
+    }
+
+    run_fix(&check.name, fix)?;
+    fixed.push(&check.name);
+    outcomes = run_selected(checks, branch, options)?;
+  }
+
+  if let Some((path, format)) = options.report {
+    check_report::write(path, format, checks, &outcomes)?;
+  }
+
+  checks::ensure_passed(&outcomes, options.stream)?;
+  Ok(outcomes)
+}
+
+/// Pick the checks to run, skipping those whose conditions don't match, and run them.
+///
+/// Checks that already passed against identical content are taken from the cache unless
+/// `use_cache` is false.
+fn run_selected(
+  checks: &[Check], branch: &str, options: &RunOptions,
+) -> Result<Vec<CheckOutcome>> {
+  let selected = match options.names {
+    [] if checks.is_empty() => return Ok(Vec::new()),
+    [] => {
+      let changed = match options.changed {
+        Some(changed) => changed.to_vec(),
+        None => git::changed_paths(&git::resolve_base()?)?,
+      };
+      let (selected, skipped) = checks::select(checks, branch, &changed)?;
+      for skip in &skipped {
+        println!("skipped check: {} ({})", skip.check.name, skip.reason);
+      }
+      selected
+    },
+    names => by_name(checks, names)?,
+  };
+
+  let mut cache = CheckCache::load()?;
+  let mut outcomes = Vec::new();
+  let mut to_run = Vec::new();
+  for check in selected {
+    match cache.lookup(check).filter(|_| options.use_cache) {
+      Some(cached) => {
+        let secs = cached.duration.as_secs_f64();
+        println!("cached check: {} (passed in {secs:.1}s on this content)", check.name);
+        outcomes.push(cached);
+      },
+      None => to_run.push(check),
+    }
+  }
+
+  let ran = checks::run(&to_run, options.stream)?;
+  if options.record {
+    cache.record(&to_run, &ran)?;
+  }
+
+  outcomes.extend(ran);
+  Ok(outcomes)
+}
+
+/// The named checks in config order, bailing on any name the config doesn't define.
+fn by_name<'a>(checks: &'a [Check], names: &[String]) -> Result<Vec<&'a Check>> {
+  if let Some(unknown) = names.iter().find(|n| !checks.iter().any(|c| &c.name == *n)) {
+    let available: Vec<&str> = checks.iter().map(|c| c.name.as_str()).collect();
+    anyhow::bail!("no check named '{unknown}' (available: {})", available.join(", "));
+  }
+
+  Ok(checks.iter().filter(|c| names.contains(&c.name)).collect())
+}
+
+/// Run a check's fixer, show the diff it produced, and stage the files it touched.
+fn run_fix(name: &str, fix: &str) -> Result<()> {
+  let before = git::working_tree_hash()?;
+
+  println!("running fix for {name}: {fix}");
+  let status = Command::new("sh")
+    .args(["-c", fix])
+    .status()
+    .with_context(|| format!("failed to run fix for check '{name}'"))?;
+
+  if !status.success() {
+    anyhow::bail!("fix for check '{name}' failed");
+  }
+
+  let after = git::working_tree_hash()?;
+  if before == after {
+    println!("fix for {name} changed nothing");
+    return Ok(());
+  }
+
+  Command::new("git")
+    .args(["diff", &before, &after])
+    .status()
+    .context("failed to run git diff")?;
+
+  let touched = git::stdout(&["diff", "--name-only", &before, &after])?;
+  let exclude = config::local_file_exclude();
+  let mut add = vec!["add", "-A", "--"];
+  add.extend(touched.lines());
+  add.push(&exclude);
+  git::stdout(&add)?;
+
+  Ok(())
+}
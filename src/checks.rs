@@ -0,0 +1,419 @@
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use anyhow::Result;
+use glob::MatchOptions;
+use glob::Pattern;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Process groups of the checks running right now, for the interrupt handler to take down.
+static RUNNING: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// A pre-ship check from `.mrt.toml`.
+#[derive(Deserialize, Serialize)]
+pub struct Check {
+  pub name: String,
+  pub command: String,
+  /// Kill the check if it runs longer than this many seconds
+  #[serde(default)]
+  pub timeout: Option<u64>,
+  /// Run at the same time as the other `parallel` checks
+  #[serde(default)]
+  pub parallel: bool,
+  /// Run at the same time as the other checks in this group
+  #[serde(default)]
+  pub group: Option<String>,
+  /// Only run when a changed path matches one of these globs
+  #[serde(default)]
+  pub paths: Vec<String>,
+  /// Only run when the current branch matches this glob
+  #[serde(default)]
+  pub branches: Option<String>,
+  /// Command that repairs what this check complains about, e.g. `cargo fmt`
+  #[serde(default)]
+  pub fix: Option<String>,
+}
+
+impl Check {
+  /// Why this check doesn't apply to the branch and changed paths, or `None` if it does.
+  fn skip_reason(&self, branch: &str, changed: &[String]) -> Result<Option<String>> {
+    if let Some(branches) = &self.branches
+      && !pattern(&self.name, branches)?.matches_with(branch, GLOB_OPTIONS)
+    {
+      return Ok(Some(format!("branch {branch} does not match {branches}")));
+    }
+
+    if self.paths.is_empty() {
+      return Ok(None);
+    }
+
+    let patterns: Vec<Pattern> =
+      self.paths.iter().map(|p| pattern(&self.name, p)).collect::<Result<_>>()?;
+    let matched =
+      changed.iter().any(|path| patterns.iter().any(|p| p.matches_with(path, GLOB_OPTIONS)));
+
+    match matched {
+      true => Ok(None),
+      false => Ok(Some(format!("no changed paths match {}", self.paths.join(", ")))),
+    }
+  }
+
+  /// Checks sharing a key run together. `None` means the check runs on its own.
+  fn stage_key(&self) -> Option<&str> {
+    self.group.as_deref().or(self.parallel.then_some(""))
+  }
+}
+
+pub enum CheckStatus {
+  Passed,
+  Failed(Option<i32>),
+  TimedOut(u64),
+}
+
+pub struct CheckOutcome {
+  pub name: String,
+  pub status: CheckStatus,
+  pub duration: Duration,
+  pub stdout: String,
+  pub stderr: String,
+}
+
+impl CheckOutcome {
+  pub fn passed(&self) -> bool {
+    matches!(self.status, CheckStatus::Passed)
+  }
+}
+
+/// A check left out of this run, and why.
+pub struct Skipped<'a> {
+  pub check: &'a Check,
+  pub reason: String,
+}
+
+/// `*` stays within a path segment, `**` crosses them.
+pub const GLOB_OPTIONS: MatchOptions = MatchOptions {
+  case_sensitive: true,
+  require_literal_separator: true,
+  require_literal_leading_dot: false,
+};
+
+/// Split checks into those whose `branches` and `paths` conditions match, and those skipped.
+pub fn select<'a>(
+  checks: &'a [Check], branch: &str, changed: &[String],
+) -> Result<(Vec<&'a Check>, Vec<Skipped<'a>>)> {
+  let mut selected = Vec::new();
+  let mut skipped = Vec::new();
+
+  for check in checks {
+    match check.skip_reason(branch, changed)? {
+      Some(reason) => skipped.push(Skipped { check, reason }),
+      None => selected.push(check),
+    }
+  }
+
+  Ok((selected, skipped))
+}
+
+/// Run checks stage by stage, stopping after the first stage with a failure.
+///
+/// Stages run in the order they first appear in the config, and the checks inside a stage run
+/// concurrently. With `stream`, output is echoed line by line as it arrives, prefixed with the
+/// check's name, as well as being captured.
+pub fn run(checks: &[&Check], stream: bool) -> Result<Vec<CheckOutcome>> {
+  forward_interrupts();
+  let mut outcomes = Vec::new();
+
+  for stage in stages(checks) {
+    for check in &stage {
+      println!("running check: {}", check.name);
+    }
+
+    let results: Vec<Result<CheckOutcome>> = thread::scope(|s| {
+      let handles: Vec<_> = stage.iter().map(|check| s.spawn(|| run_one(check, stream))).collect();
+      handles.into_iter().map(|h| h.join().expect("check thread panicked")).collect()
+    });
+
+    let mut failed = false;
+    for result in results {
+      let outcome = result?;
+      failed |= !outcome.passed();
+      outcomes.push(outcome);
+    }
+
+    if failed {
+      break;
+    }
+  }
+
+  Ok(outcomes)
+}
+
+/// Print the output of every failed check, unless it was already streamed, and bail if there
+/// were any.
+pub fn ensure_passed(outcomes: &[CheckOutcome], streamed: bool) -> Result<()> {
+  let failures: Vec<&CheckOutcome> = outcomes.iter().filter(|o| !o.passed()).collect();
+
+  for outcome in &failures {
+    if !streamed && !outcome.stdout.is_empty() {
+      eprintln!("{}", outcome.stdout);
+    }
+    if !streamed && !outcome.stderr.is_empty() {
+      eprintln!("{}", outcome.stderr);
+    }
+    if let CheckStatus::TimedOut(secs) = outcome.status {
+      eprintln!("check '{}' timed out after {secs}s and was killed", outcome.name);
+    }
+  }
+
+  match failures.as_slice() {
+    [] => Ok(()),
+    [only] => anyhow::bail!("check '{}' failed", only.name),
+    many => {
+      let names: Vec<&str> = many.iter().map(|o| o.name.as_str()).collect();
+      anyhow::bail!("checks failed: {}", names.join(", "))
+    },
+  }
+}
+
+fn stages<'a>(checks: &[&'a Check]) -> Vec<Vec<&'a Check>> {
+  let mut stages: Vec<(Option<&str>, Vec<&Check>)> = Vec::new();
+
+  for &check in checks {
+    let key = check.stage_key();
+    match stages.iter_mut().find(|(k, _)| key.is_some() && *k == key) {
+      Some((_, stage)) => stage.push(check),
+      None => stages.push((key, vec![check])),
+    }
+  }
+
+  stages.into_iter().map(|(_, stage)| stage).collect()
+}
+
+fn run_one(check: &Check, stream: bool) -> Result<CheckOutcome> {
+  let start = Instant::now();
+
+  // Put the check in its own process group so a timeout can kill everything `sh` spawned. That
+  // also keeps it from the terminal's Ctrl-C, which `forward_interrupts` makes up for.
+  let mut child = Command::new("sh")
+    .args(["-c", &check.command])
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .process_group(0)
+    .spawn()
+    .with_context(|| format!("failed to run check '{}'", check.name))?;
+  running(|groups| groups.push(child.id()));
+
+  let prefix = stream.then(|| format!("[{}] ", check.name));
+  let stdout = drain(child.stdout.take(), prefix.clone());
+  let stderr = drain(child.stderr.take(), prefix);
+  let deadline = check.timeout.map(|secs| start + Duration::from_secs(secs));
+
+  let status = loop {
+    if let Some(exit) = child.try_wait()? {
+      break match exit.success() {
+        true => CheckStatus::Passed,
+        false => CheckStatus::Failed(exit.code()),
+      };
+    }
+
+    if let (Some(deadline), Some(secs)) = (deadline, check.timeout)
+      && Instant::now() >= deadline
+    {
+      kill_group(&mut child);
+      break CheckStatus::TimedOut(secs);
+    }
+
+    thread::sleep(Duration::from_millis(50));
+  };
+  running(|groups| groups.retain(|&id| id != child.id()));
+
+  let outcome = CheckOutcome {
+    name: check.name.clone(),
+    status,
+    duration: start.elapsed(),
+    stdout: stdout.join().unwrap_or_default(),
+    stderr: stderr.join().unwrap_or_default(),
+  };
+
+  let label = match outcome.status {
+    CheckStatus::Passed => "passed".to_string(),
+    CheckStatus::Failed(Some(code)) => format!("failed (exit code {code})"),
+    CheckStatus::Failed(None) => "failed (killed by signal)".to_string(),
+    CheckStatus::TimedOut(secs) => format!("timed out after {secs}s"),
+  };
+  println!("  {}: {label} ({:.1}s)", outcome.name, outcome.duration.as_secs_f64());
+
+  Ok(outcome)
+}
+
+fn pattern(check: &str, glob: &str) -> Result<Pattern> {
+  Pattern::new(glob).with_context(|| format!("invalid glob '{glob}' in check '{check}'"))
+}
+
+/// Read a child pipe to completion on a background thread, echoing each line after `prefix`
+/// when there is one.
+fn drain(
+  pipe: Option<impl Read + Send + 'static>, prefix: Option<String>,
+) -> thread::JoinHandle<String> {
+  thread::spawn(move || {
+    let mut buf = Vec::new();
+    let Some(pipe) = pipe else {
+      return String::new();
+    };
+
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+      if let Some(prefix) = &prefix {
+        print!("{prefix}{}", String::from_utf8_lossy(&line));
+        if !line.ends_with(b"\n") {
+          println!();
+        }
+      }
+      buf.append(&mut line);
+    }
+    String::from_utf8_lossy(&buf).into_owned()
+  })
+}
+
+fn kill_group(child: &mut Child) {
+  let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).status();
+  let _ = child.kill();
+  let _ = child.wait();
+}
+
+fn running<T>(access: impl FnOnce(&mut Vec<u32>) -> T) -> T {
+  let mut groups = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+  access(&mut groups)
+}
+
+/// On SIGINT, SIGTERM or SIGHUP, pass the signal on to the running checks' process groups as a
+/// SIGTERM, then exit as an interrupted process would.
+fn forward_interrupts() {
+  static INSTALLED: Once = Once::new();
+
+  INSTALLED.call_once(|| {
+    let _ = ctrlc::set_handler(|| {
+      for group in running(|groups| groups.clone()) {
+        let _ = Command::new("kill").args(["-TERM", "--", &format!("-{group}")]).status();
+      }
+      std::process::exit(130);
+    });
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn check(name: &str) -> Check {
+    Check {
+      name: name.to_string(),
+      command: "true".to_string(),
+      timeout: None,
+      parallel: false,
+      group: None,
+      paths: Vec::new(),
+      branches: None,
+      fix: None,
+    }
+  }
+
+  fn names(stages: Vec<Vec<&Check>>) -> Vec<Vec<&str>> {
+    stages.iter().map(|stage| stage.iter().map(|c| c.name.as_str()).collect()).collect()
+  }
+
+  #[test]
+  fn stages_group_checks_in_order_of_first_appearance() {
+    let fmt = check("fmt");
+    let lint = Check { parallel: true, ..check("lint") };
+    let unit = Check { group: Some("test".to_string()), ..check("unit") };
+    let audit = Check { parallel: true, ..check("audit") };
+    let doc = check("doc");
+    let integration = Check { group: Some("test".to_string()), ..check("integration") };
+
+    let checks = [&fmt, &lint, &unit, &audit, &doc, &integration];
+    assert_eq!(
+      names(stages(&checks)),
+      [vec!["fmt"], vec!["lint", "audit"], vec!["unit", "integration"], vec!["doc"]],
+    );
+  }
+
+  #[test]
+  fn stages_keep_ungrouped_checks_apart() {
+    let (a, b) = (check("a"), check("b"));
+    assert_eq!(names(stages(&[&a, &b])), [vec!["a"], vec!["b"]]);
+  }
+
+  #[test]
+  fn run_times_out_and_kills_the_check() {
+    let slow = Check { timeout: Some(1), command: "sleep 30".to_string(), ..check("slow") };
+    let outcomes = run(&[&slow], false).unwrap();
+    assert!(matches!(outcomes[0].status, CheckStatus::TimedOut(1)));
+    assert!(outcomes[0].duration < Duration::from_secs(5));
+  }
+
+  #[test]
+  fn run_stops_after_a_failing_stage() {
+    let failing = Check { command: "exit 3".to_string(), ..check("failing") };
+    let after = check("after");
+    let outcomes = run(&[&failing, &after], false).unwrap();
+    assert_eq!(outcomes.len(), 1);
+    assert!(matches!(outcomes[0].status, CheckStatus::Failed(Some(3))));
+  }
+
+  #[test]
+  fn skip_reason_matches_paths_and_branches() {
+    let changed = ["src/main.rs".to_string(), "docs/guide.md".to_string()];
+    let rust = Check { paths: vec!["src/**/*.rs".to_string()], ..check("rust") };
+    let web = Check { paths: vec!["web/**".to_string()], ..check("web") };
+    let release = Check { branches: Some("release/*".to_string()), ..check("release") };
+
+    assert_eq!(rust.skip_reason("main", &changed).unwrap(), None);
+    assert_eq!(
+      web.skip_reason("main", &changed).unwrap().as_deref(),
+      Some("no changed paths match web/**"),
+    );
+    assert_eq!(release.skip_reason("release/1.2", &changed).unwrap(), None);
+    assert_eq!(
+      release.skip_reason("release/1.2/hotfix", &changed).unwrap().as_deref(),
+      Some("branch release/1.2/hotfix does not match release/*"),
+    );
+  }
+
+  #[test]
+  fn single_star_stays_within_a_path_segment() {
+    let top = Check { paths: vec!["*.md".to_string()], ..check("top") };
+    let nested = ["docs/guide.md".to_string()];
+    assert!(top.skip_reason("main", &nested).unwrap().is_some());
+    assert!(top.skip_reason("main", &["README.md".to_string()]).unwrap().is_none());
+  }
+
+  #[test]
+  fn select_reports_invalid_globs() {
+    let broken = Check { paths: vec!["src/[".to_string()], ..check("broken") };
+    let error = select(std::slice::from_ref(&broken), "main", &[]).err().unwrap();
+    assert_eq!(error.to_string(), "invalid glob 'src/[' in check 'broken'");
+  }
+
+  #[test]
+  fn select_splits_applicable_and_skipped_checks() {
+    let checks = [check("always"), Check { paths: vec!["web/**".to_string()], ..check("web") }];
+    let (selected, skipped) = select(&checks, "main", &["src/lib.rs".to_string()]).unwrap();
+    assert_eq!(selected.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["always"]);
+    assert_eq!(skipped.iter().map(|s| s.check.name.as_str()).collect::<Vec<_>>(), ["web"]);
+  }
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::forge::CheckRun;
+use crate::forge::CheckState;
+use crate::forge::Forge;
+use crate::forge::PrStatus;
+use crate::utils::clear_screen;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Poll the PR's check runs and merge state, redrawing a status table until it resolves.
+///
+/// Fails as soon as a required check fails, printing the tail of its log. Succeeds once the PR
+/// is merged. When `until_merged` is false, nothing will merge it, so the watch also ends once no
+/// check is pending: failing if any check failed, required or not, and succeeding otherwise.
+pub fn watch(forge: &dyn Forge, pr_url: &str, until_merged: bool) -> Result<()> {
+  loop {
+    let runs = forge.checks(pr_url, false)?;
+    let required = forge.checks(pr_url, true)?;
+    let pr = forge.pr_state(pr_url)?;
+
+    clear_screen();
+    println!("{pr_url}  {}\n", pr.detail);
+    for run in &runs {
+      println!("  {:<9} {}", run.state.label(), run.name);
+    }
+    std::io::stdout().flush()?;
+
+    if let Some(failed) = required.iter().find(|r| r.state == CheckState::Fail) {
+      print_failure(forge, failed);
+      anyhow::bail!("required check '{}' failed", failed.name);
+    }
+
+    match pr.status {
+      PrStatus::Merged => {
+        println!("\nmerged");
+        return Ok(());
+      },
+      PrStatus::Closed => anyhow::bail!("PR was closed without merging"),
+      PrStatus::Open => {},
+    }
+
+    let settled = runs.iter().all(|r| r.state != CheckState::Pending);
+    if !until_merged && !runs.is_empty() && settled {
+      if let Some(failed) = runs.iter().find(|r| r.state == CheckState::Fail) {
+        print_failure(forge, failed);
+        anyhow::bail!("check '{}' failed", failed.name);
+      }
+      println!("\nall checks passed");
+      return Ok(());
+    }
+
+    thread::sleep(POLL_INTERVAL);
+  }
+}
+
+fn print_failure(forge: &dyn Forge, run: &CheckRun) {
+  println!("\n{} failed: {}", run.name, run.link);
+
+  if let Some(log) = forge.failed_log(run) {
+    println!("\n{log}");
+  }
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use clap::Parser;
+
+use crate::check_report::ReportFormat;
+use crate::check_runner;
+use crate::check_runner::FixMode;
+use crate::check_runner::RunOptions;
+use crate::checks::Check;
+use crate::config::MrtConfig;
+use crate::git;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run the .mrt.toml checks that ship would run
+#[derive(Parser)]
+pub struct CheckCommand {
+  /// Run only these checks, even if their `paths` or `branches` conditions don't match
+  pub names: Vec<String>,
+  /// List the configured checks and their conditions instead of running them
+  #[arg(long, conflicts_with_all = ["stream", "watch", "autofix", "report"])]
+  pub list: bool,
+  /// Print check output as it arrives instead of only when a check fails
+  #[arg(long)]
+  pub stream: bool,
+  /// Keep running, re-running the checks affected whenever files change
+  #[arg(long)]
+  pub watch: bool,
+  /// Run every check even if it already passed against identical content
+  #[arg(long)]
+  pub no_cache: bool,
+  /// Run the `fix` command of failing checks without asking
+  #[arg(long)]
+  pub autofix: bool,
+  /// Write check results to this file, as JUnit XML if it ends in `.xml` and JSON otherwise
+  #[arg(long, value_name = "PATH")]
+  pub report: Option<PathBuf>,
+  /// Format of the `--report` file, overriding the one implied by its extension
+  #[arg(long, value_enum, requires = "report")]
+  pub report_format: Option<ReportFormat>,
+}
+
+impl CheckCommand {
+  pub fn execute(self) -> Result<()> {
+    let config = MrtConfig::load_strict()?;
+    if config.checks.is_empty() {
+      anyhow::bail!("no checks configured in .mrt.toml");
+    }
+
+    if self.list {
+      print_list(&config.checks);
+      return Ok(());
+    }
+
+    let branch = git::current_branch()?;
+    if !self.watch {
+      check_runner::run(&config.checks, &branch, &self.options(None))?;
+      return Ok(());
+    }
+
+    let mut tree = git::working_tree_hash()?;
+    let mut changed = None;
+    loop {
+      let options = self.options(changed.as_deref());
+      if let Err(e) = check_runner::run(&config.checks, &branch, &options) {
+        eprintln!("{e:#}");
+      }
+      println!("\nwatching for changes (Ctrl-C to stop)");
+
+      let (next, paths) = wait_for_change(&tree)?;
+      println!("\nchanged: {}", paths.join(", "));
+      tree = next;
+      changed = Some(paths);
+    }
+  }
+
+  fn options<'a>(&'a self, changed: Option<&'a [String]>) -> RunOptions<'a> {
+    let report = self
+      .report
+      .as_deref()
+      .map(|path| (path, self.report_format.unwrap_or_else(|| ReportFormat::from_path(path))));
+
+    RunOptions {
+      names: &self.names,
+      changed,
+      use_cache: !self.no_cache,
+      record: true,
+      stream: self.stream,
+      fix: if self.autofix { FixMode::Always } else { FixMode::Ask },
+      report,
+    }
+  }
+}
+
+/// Block until the working tree hashes differ from `tree`, returning the new hash and the paths
+/// that changed.
+fn wait_for_change(tree: &str) -> Result<(String, Vec<String>)> {
+  loop {
+    thread::sleep(WATCH_INTERVAL);
+
+    let next = git::working_tree_hash()?;
+    if next == tree {
+      continue;
+    }
+
+    let diff = git::stdout(&["diff", "--name-only", "--no-renames", tree, &next])?;
+    return Ok((next, diff.lines().map(str::to_string).collect()));
+  }
+}
+
+fn print_list(checks: &[Check]) {
+  for check in checks {
+    println!("{}: {}", check.name, check.command);
+
+    let mut conditions = Vec::new();
+    if let Some(group) = &check.group {
+      conditions.push(format!("group {group}"));
+    } else if check.parallel {
+      conditions.push("parallel".to_string());
+    }
+    if !check.paths.is_empty() {
+      conditions.push(format!("paths {}", check.paths.join(", ")));
+    }
+    if let Some(branches) = &check.branches {
+      conditions.push(format!("branches {branches}"));
+    }
+    if let Some(secs) = check.timeout {
+      conditions.push(format!("timeout {secs}s"));
+    }
+    if let Some(fix) = &check.fix {
+      conditions.push(format!("fix `{fix}`"));
+    }
+
+    if !conditions.is_empty() {
+      println!("  {}", conditions.join("; "));
+    }
+  }
+}
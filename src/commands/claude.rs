@@ -3,15 +3,16 @@ use std::process::Command;
 use anyhow::Result;
 use clap::Parser;
 
+use crate::config::MrtConfig;
 use crate::window;
+use crate::workspace;
 
 #[derive(Parser)]
 pub struct ClaudeCommand;
 
 impl ClaudeCommand {
   pub fn execute(self) -> Result<()> {
-    let home = std::env::var("HOME")?;
-    let projects_dir = format!("{home}/projects");
+    let projects_dir = workspace::primary_root(&MrtConfig::load()?)?;
 
     let _ = window::snap_active_right();
 
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use clap::Parser;
+use toml::Table;
+use toml::Value;
+
+use crate::config::Layers;
+use crate::config::MrtConfig;
+use crate::config_schema;
+
+/// Inspect mrt's merged configuration
+#[derive(Parser)]
+pub struct ConfigCommand {
+  #[command(subcommand)]
+  action: ConfigAction,
+}
+
+#[derive(clap::Subcommand)]
+enum ConfigAction {
+  /// Print the effective config, noting which layer each value came from
+  Show,
+  /// Check every config file for unknown keys and wrong types
+  Validate,
+  /// Print the JSON Schema for config files, for editor completion
+  Schema,
+}
+
+impl ConfigCommand {
+  pub fn execute(self) -> Result<()> {
+    match self.action {
+      ConfigAction::Show => show(),
+      ConfigAction::Validate => validate(),
+      ConfigAction::Schema => {
+        println!("{}", serde_json::to_string_pretty(&config_schema::schema())?);
+        Ok(())
+      },
+    }
+  }
+}
+
+fn show() -> Result<()> {
+  let layers = Layers::load()?;
+  let (merged, sources) = layers.merge();
+  let labels: Vec<String> = layers.0.iter().map(|layer| layer.label()).collect();
+
+  println!("# layers, lowest precedence first:");
+  for label in &labels {
+    println!("#   {label}");
+  }
+
+  let source =
+    |path: &str| sources.get(path).map_or("default".to_string(), |&i| labels[i].clone());
+  print_table(&merged, "", &source);
+  Ok(())
+}
+
+fn validate() -> Result<()> {
+  let layers = Layers::load()?;
+  let files: Vec<_> = layers.0.iter().filter(|layer| layer.path.is_some()).collect();
+
+  let mut count = 0;
+  for layer in &files {
+    for problem in layer.problems() {
+      println!("{}: `{}` {}", layer.label(), problem.key, problem.message);
+      count += 1;
+    }
+  }
+
+  if let Err(e) = MrtConfig::from_layers(&layers) {
+    println!("merged config: {:#}", e);
+    count += 1;
+  }
+
+  match count {
+    0 => {
+      println!("{} config file(s) valid", files.len());
+      Ok(())
+    },
+    1 => anyhow::bail!("1 problem found"),
+    n => anyhow::bail!("{n} problems found"),
+  }
+}
+
+/// Print a table as TOML with each value annotated by its source. Plain values come first, then
+/// sub-tables, then arrays of tables, so the output parses back to the same config.
+fn print_table(table: &Table, prefix: &str, source: &dyn Fn(&str) -> String) {
+  let path = |key: &str| match prefix {
+    "" => key.to_string(),
+    prefix => format!("{prefix}.{key}"),
+  };
+
+  for (key, value) in table {
+    if !matches!(value, Value::Table(_)) && !is_table_array(value) {
+      println!("{key} = {value}  # {}", source(&path(key)));
+    }
+  }
+
+  for (key, value) in table {
+    let Value::Table(sub) = value else {
+      continue;
+    };
+
+    let sub_path = path(key);
+    if sub.values().any(|v| !matches!(v, Value::Table(_)) && !is_table_array(v)) {
+      println!("\n[{sub_path}]");
+    }
+    print_table(sub, &sub_path, source);
+  }
+
+  for (key, value) in table {
+    let Value::Array(items) = value else {
+      continue;
+    };
+    if !is_table_array(value) {
+      continue;
+    }
+
+    let array_path = path(key);
+    for item in items.iter().filter_map(Value::as_table) {
+      println!("\n[[{array_path}]]  # {}", source(&array_path));
+      for (key, value) in item {
+        println!("{key} = {value}");
+      }
+    }
+  }
+}
+
+fn is_table_array(value: &Value) -> bool {
+  matches!(value, Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_table))
+}
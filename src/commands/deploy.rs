@@ -4,48 +4,36 @@ use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
 
-// ── Deploy targets ──────────────────────────────────────────────────
-// Each entry defines a deploy target: (variant name, help text, host, remote command)
-
-struct Target {
-  host: &'static str,
-  command: &'static str,
-}
-
-const PDQ_STUDIO: Target = Target {
-  host: "krjr84",
-  command: "sudo -iu lewis bash -lc 'cd ~/pdq-studio && git pull && bun run build' && sudo \
-            systemctl restart pdq-studio",
-};
-
-// ─────────────────────────────────────────────────────────────────────
+use crate::config::DeployTarget;
+use crate::config::MrtConfig;
 
 /// Deploy updates to remote services
 #[derive(Parser)]
 pub struct DeployCommand {
-  #[command(subcommand)]
-  target: DeployTarget,
-}
-
-#[derive(clap::Subcommand)]
-enum DeployTarget {
-  /// Update pdq-studio on krjr84 from main
-  PdqStudio,
+  /// A `[deploy.<target>]` name from the config, e.g. pdq-studio
+  target: String,
 }
 
 impl DeployCommand {
   pub fn execute(self) -> Result<()> {
-    match self.target {
-      DeployTarget::PdqStudio => deploy(&PDQ_STUDIO, "pdq-studio"),
-    }
+    let config = MrtConfig::load()?;
+    let name = self.target.as_str();
+    let Some(target) = config.deploy.get(name) else {
+      let available: Vec<&str> = config.deploy.keys().map(String::as_str).collect();
+      match available.as_slice() {
+        [] => anyhow::bail!("no deploy target '{name}' (none configured)"),
+        _ => anyhow::bail!("no deploy target '{name}' (available: {})", available.join(", ")),
+      }
+    };
+    deploy(target, name)
   }
 }
 
-fn deploy(target: &Target, name: &str) -> Result<()> {
+fn deploy(target: &DeployTarget, name: &str) -> Result<()> {
   println!("Deploying {name} on {}...", target.host);
 
   let status = Command::new("ssh")
-    .args([target.host, target.command])
+    .args([&target.host, &target.command])
     .status()
     .with_context(|| format!("failed to ssh to {}", target.host))?;
 
@@ -1,35 +1,130 @@
+use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
 
 use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
+use serde::Serialize;
 
+use crate::config::MrtConfig;
+use crate::editor::EditorKind;
+use crate::fix_worktree;
+use crate::fix_worktree::FixWorktree;
+use crate::forge;
+use crate::forge::BranchPr;
+use crate::forge::Forge;
+use crate::forge::Issue;
+use crate::forge::PrStatus;
+use crate::git;
 use crate::name_generator::generate_name;
+use crate::utils::choose;
+use crate::utils::confirm;
 use crate::window;
+use crate::workspace;
+use crate::worktree_meta::WorktreeMeta;
+
+/// Longest issue title slug in a branch name, in bytes.
+const MAX_SLUG_LEN: usize = 40;
 
 /// Start a fix workflow for a repository
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct FixCommand {
-  /// Name of the repository under ~/projects/
-  pub repo: String,
+  #[command(subcommand)]
+  action: Option<FixAction>,
+  /// Name of the repository under one of the projects roots
+  #[arg(required = true)]
+  pub repo: Option<String>,
+  /// Name the branch after this issue, and have the PR shipped from it close the issue
+  #[arg(long, value_name = "NUMBER")]
+  pub issue: Option<u64>,
+  /// Start from this branch, tag or commit, or `pr:<number>` for a PR's head, instead of the
+  /// default branch
+  #[arg(long, value_name = "REF")]
+  pub from: Option<String>,
+}
+
+#[derive(clap::Subcommand)]
+enum FixAction {
+  /// Show the fix worktrees of every repo under the projects roots
+  List {
+    /// Print the worktrees as JSON
+    #[arg(long)]
+    json: bool,
+  },
+  /// Remove fix worktrees whose branch was merged or whose PR was merged or closed
+  Clean {
+    /// Also remove worktrees with uncommitted changes
+    #[arg(long)]
+    force: bool,
+    /// Don't ask before removing
+    #[arg(long, short)]
+    yes: bool,
+  },
+  /// Reopen the editor on an existing fix worktree and snap the windows back into place
+  Resume {
+    /// Only consider this repository's fix worktrees
+    repo: Option<String>,
+    /// The fix branch, with or without its `fix/` prefix
+    branch: Option<String>,
+  },
+}
+
+/// One row of `mrt fix list`.
+#[derive(Serialize)]
+struct Listing {
+  repo: String,
+  branch: String,
+  path: PathBuf,
+  age_secs: Option<u64>,
+  dirty: Option<bool>,
+  ahead: Option<u32>,
+  behind: Option<u32>,
+  pr_state: Option<&'static str>,
+  pr_url: Option<String>,
 }
 
 impl FixCommand {
   pub fn execute(self) -> Result<()> {
-    let repo_dir = repo_dir(&self.repo);
-
-    if !repo_dir.exists() {
-      anyhow::bail!("repository not found: {}", repo_dir.display());
+    if let Some(action) = self.action {
+      return action.execute();
     }
 
-    let base_ref = fetch_and_resolve_base(&repo_dir)?;
+    let repo = self.repo.context("no repository given")?;
+    let repo_dir = workspace::find_repo(&MrtConfig::load()?, &repo)?;
 
-    let branch = loop {
-      let candidate = format!("fix/{}", generate_name());
-      if !branch_exists(&repo_dir, &candidate)? {
-        break candidate;
-      }
+    let default_base = fetch_and_resolve_base(&repo_dir)?;
+
+    let mut meta = WorktreeMeta::default();
+    let start = match &self.from {
+      Some(from) => {
+        let (start, base) = resolve_from(&repo_dir, from)?;
+        meta.base = base;
+        start
+      },
+      None => default_base,
+    };
+
+    let branch = match self.issue {
+      Some(number) => {
+        let issue = find_issue(&repo_dir, number)?;
+        let branch = issue_branch(number, &issue.title);
+        if branch_exists(&repo_dir, &branch)? {
+          anyhow::bail!("branch {branch} already exists");
+        }
+
+        println!("issue #{number}: {}", issue.title);
+        meta.issue = Some(format!("#{number}"));
+        meta.issue_url = Some(issue.url);
+        branch
+      },
+      None => loop {
+        let candidate = format!("fix/{}", generate_name());
+        if !branch_exists(&repo_dir, &candidate)? {
+          break candidate;
+        }
+      },
     };
 
     let worktree_dir = repo_dir.join(".worktrees").join(&branch);
@@ -37,7 +132,7 @@ impl FixCommand {
     let status = Command::new("git")
       .args(["worktree", "add", "-b", &branch])
       .arg(&worktree_dir)
-      .arg(&base_ref)
+      .arg(&start)
       .current_dir(&repo_dir)
       .status()
       .context("failed to run git worktree add")?;
@@ -45,31 +140,297 @@ impl FixCommand {
     if !status.success() {
       anyhow::bail!("git worktree add failed");
     }
+    meta.save_in(&worktree_dir)?;
 
     println!("branch: {branch}");
+    if let Some(base) = &meta.base {
+      println!("base: {base}");
+    }
     println!("worktree: {}", worktree_dir.display());
 
-    // Snap the terminal to the right half
-    let _ = window::snap_active_right();
+    open_editor(&worktree_dir)
+  }
+}
+
+impl FixAction {
+  fn execute(self) -> Result<()> {
+    match self {
+      FixAction::List { json } => list(json),
+      FixAction::Clean { force, yes } => clean(force, yes),
+      FixAction::Resume { repo, branch } => resume(repo.as_deref(), branch.as_deref()),
+    }
+  }
+}
 
-    Command::new("code").arg(&worktree_dir).spawn()?;
+fn list(json: bool) -> Result<()> {
+  let worktrees = fix_worktree::all(&MrtConfig::load()?)?;
 
-    // The VS Code window title will contain the worktree folder name.
-    // snap_window_left waits for the window to appear before positioning.
-    let window_title = worktree_dir.file_name().unwrap().to_str().unwrap();
-    let _ = window::snap_window_left(window_title);
+  let mut listings = Vec::new();
+  for worktree in &worktrees {
+    let (ahead, behind) = worktree.ahead_behind().ok().unzip();
+    let pr = latest_pr(worktree);
+    listings.push(Listing {
+      repo: worktree.repo.clone(),
+      branch: worktree.branch.clone(),
+      path: worktree.path.clone(),
+      age_secs: worktree.age().map(|age| age.as_secs()),
+      dirty: worktree.is_dirty().ok(),
+      ahead,
+      behind,
+      pr_state: pr.as_ref().map(|pr| pr.status.label()),
+      pr_url: pr.map(|pr| pr.url),
+    });
+  }
 
-    Ok(())
+  if json {
+    println!("{}", serde_json::to_string_pretty(&listings)?);
+    return Ok(());
   }
+
+  if listings.is_empty() {
+    println!("no fix worktrees");
+    return Ok(());
+  }
+
+  let unknown = || "?".to_string();
+  let rows: Vec<[String; 7]> = listings
+    .iter()
+    .map(|l| {
+      [
+        l.repo.clone(),
+        l.branch.clone(),
+        l.age_secs.map(age_label).unwrap_or_else(unknown),
+        l.dirty.map(|d| if d { "dirty" } else { "clean" }.to_string()).unwrap_or_else(unknown),
+        l.ahead.zip(l.behind).map(|(a, b)| format!("+{a} -{b}")).unwrap_or_else(unknown),
+        l.pr_state.unwrap_or("-").to_string(),
+        l.path.display().to_string(),
+      ]
+    })
+    .collect();
+
+  let header =
+    ["REPO", "BRANCH", "AGE", "STATE", "AHEAD/BEHIND", "PR", "PATH"].map(str::to_string);
+  let mut widths = [0; 7];
+  for row in std::iter::once(&header).chain(&rows) {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.len());
+    }
+  }
+
+  for row in std::iter::once(&header).chain(&rows) {
+    let cells: Vec<String> =
+      row.iter().zip(widths).map(|(cell, width)| format!("{cell:<width$}")).collect();
+    println!("{}", cells.join("  ").trim_end());
+  }
+
+  Ok(())
 }
 
-fn repo_dir(name: &str) -> PathBuf {
-  let home = std::env::var("HOME").expect("HOME not set");
-  PathBuf::from(home).join("projects").join(name)
+fn clean(force: bool, yes: bool) -> Result<()> {
+  let worktrees = fix_worktree::all(&MrtConfig::load()?)?;
+
+  let mut fetched = Vec::new();
+  let mut doomed = Vec::new();
+  for worktree in worktrees {
+    // Merged-ness is judged against origin, so bring each repo's view of it up to date once.
+    if !fetched.contains(&worktree.repo_dir) {
+      let _ = git::stdout_in(&worktree.repo_dir, &["fetch", "--quiet", "origin"]);
+      fetched.push(worktree.repo_dir.clone());
+    }
+
+    let reason = match latest_pr(&worktree).map(|pr| pr.status) {
+      Some(status @ (PrStatus::Merged | PrStatus::Closed)) => format!("PR {}", status.label()),
+      _ if worktree.is_merged()? => format!("merged into {}", worktree.base()?),
+      _ => continue,
+    };
+
+    if worktree.is_dirty()? && !force {
+      println!(
+        "keeping {} ({reason}, but it has uncommitted changes; use --force)",
+        worktree.branch
+      );
+      continue;
+    }
+
+    let size = fix_worktree::disk_usage(&worktree.path);
+    doomed.push((worktree, reason, size));
+  }
+
+  if doomed.is_empty() {
+    println!("nothing to clean");
+    return Ok(());
+  }
+
+  for (worktree, reason, size) in &doomed {
+    println!("{}  {}  {reason}  {}", worktree.repo, worktree.branch, size_label(*size));
+  }
+  let total: u64 = doomed.iter().map(|(_, _, size)| size).sum();
+  println!("\n{} worktree(s), {} reclaimed", doomed.len(), size_label(total));
+
+  if !yes && !confirm("remove them?")? {
+    return Ok(());
+  }
+
+  for (worktree, _, _) in &doomed {
+    let path = worktree.path.to_string_lossy();
+    let mut remove = vec!["worktree", "remove", path.as_ref()];
+    if force {
+      remove.push("--force");
+    }
+    git::stdout_in(&worktree.repo_dir, &remove)?;
+    // Squash merges leave the branch looking unmerged to git, so delete it regardless.
+    git::stdout_in(&worktree.repo_dir, &["branch", "-D", &worktree.branch])?;
+    println!("removed {}", worktree.branch);
+  }
+
+  Ok(())
+}
+
+fn resume(repo: Option<&str>, branch: Option<&str>) -> Result<()> {
+  let branch =
+    branch.map(|b| if b.starts_with("fix/") { b.to_string() } else { format!("fix/{b}") });
+  let mut worktrees: Vec<FixWorktree> = fix_worktree::all(&MrtConfig::load()?)?
+    .into_iter()
+    .filter(|w| repo.is_none_or(|repo| w.repo == repo))
+    .filter(|w| branch.as_ref().is_none_or(|branch| &w.branch == branch))
+    .collect();
+
+  let worktree = match worktrees.len() {
+    0 => match (repo, &branch) {
+      (Some(repo), Some(branch)) => anyhow::bail!("no fix worktree for {branch} in {repo}"),
+      (Some(repo), None) => anyhow::bail!("no fix worktrees in {repo}"),
+      _ => anyhow::bail!("no fix worktrees to resume"),
+    },
+    1 => worktrees.remove(0),
+    _ => {
+      let options: Vec<String> =
+        worktrees.iter().map(|w| format!("{}  {}", w.repo, w.branch)).collect();
+      match choose(&options)? {
+        Some(picked) => worktrees.remove(picked),
+        None => return Ok(()),
+      }
+    },
+  };
+
+  println!("worktree: {}", worktree.path.display());
+  open_editor(&worktree.path)
+}
+
+/// Open the editor on the worktree, with it on the left half of the screen and the terminal
+/// on the right.
+fn open_editor(worktree_dir: &Path) -> Result<()> {
+  let editor = MrtConfig::load()?.editor;
+  if editor.kind == EditorKind::None {
+    return Ok(());
+  }
+
+  // Snap the terminal to the right half
+  let _ = window::snap_active_right();
+
+  // snap_window_left waits for the window to appear before positioning.
+  if let Some(window_title) = editor.open(worktree_dir, None)? {
+    let _ = window::snap_window_left(&window_title);
+  }
+
+  Ok(())
+}
+
+/// The worktree's PR, if its repo's forge can be reached.
+fn latest_pr(worktree: &FixWorktree) -> Option<BranchPr> {
+  let forge = forge_in(&worktree.path).ok()?;
+  forge.latest_pr(&worktree.branch).ok()?
+}
+
+/// `45m`, `6h` or `12d`.
+fn age_label(secs: u64) -> String {
+  match secs {
+    s if s < 3600 => format!("{}m", s / 60),
+    s if s < 86400 => format!("{}h", s / 3600),
+    s => format!("{}d", s / 86400),
+  }
+}
+
+/// `512 B`, `3.4 MB` or `1.2 GB`.
+fn size_label(bytes: u64) -> String {
+  const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+  let mut size = bytes as f64;
+  let mut unit = 0;
+  while size >= 1024.0 && unit < UNITS.len() - 1 {
+    size /= 1024.0;
+    unit += 1;
+  }
+
+  match unit {
+    0 => format!("{bytes} B"),
+    _ => format!("{size:.1} {}", UNITS[unit]),
+  }
+}
+
+/// Look the issue up on the repo's forge.
+fn find_issue(repo_dir: &Path, number: u64) -> Result<Issue> {
+  let forge = forge_in(repo_dir)?;
+  forge.issue(number).with_context(|| format!("failed to look up issue #{number}"))
+}
+
+/// The forge hosting the repo at `repo_dir`.
+fn forge_in(repo_dir: &Path) -> Result<Box<dyn Forge>> {
+  forge::detect_in(repo_dir, MrtConfig::load_in(repo_dir)?.ship.forge)
+}
+
+/// The commit `--from` names, and the branch on origin the fix's PR should target if it isn't
+/// the default branch.
+fn resolve_from(repo_dir: &Path, from: &str) -> Result<(String, Option<String>)> {
+  if let Some(number) = from.strip_prefix("pr:") {
+    let number: u64 = number.parse().with_context(|| format!("not a PR number: {number}"))?;
+    let head = forge_in(repo_dir)?
+      .pr_head(number)
+      .with_context(|| format!("failed to look up PR #{number}"))?;
+
+    git::stdout_in(repo_dir, &["fetch", "--quiet", "origin", &head.fetch_ref])?;
+    let commit = git::stdout_in(repo_dir, &["rev-parse", "FETCH_HEAD"])?;
+    println!("PR #{number}: {}", head.branch);
+
+    // A PR from a fork has no branch on origin to target, so the fix goes to the default branch.
+    let base = on_origin(repo_dir, &head.branch).then_some(head.branch);
+    return Ok((commit.trim().to_string(), base));
+  }
+
+  if on_origin(repo_dir, from) {
+    return Ok((format!("origin/{from}"), Some(from.to_string())));
+  }
+
+  match git::stdout_in(
+    repo_dir,
+    &["rev-parse", "--verify", "--quiet", &format!("{from}^{{commit}}")],
+  ) {
+    Ok(commit) => Ok((commit.trim().to_string(), None)),
+    Err(_) => anyhow::bail!("no branch, tag or commit named {from}"),
+  }
+}
+
+fn on_origin(repo_dir: &Path, branch: &str) -> bool {
+  let remote = format!("refs/remotes/origin/{branch}");
+  git::stdout_in(repo_dir, &["rev-parse", "--verify", "--quiet", &remote]).is_ok()
+}
+
+/// `fix/123-null-price-crash` for issue 123, "Null price crash".
+fn issue_branch(number: u64, title: &str) -> String {
+  let mut slug = String::new();
+  let words = title.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty());
+  for word in words {
+    if slug.len() + word.len() > MAX_SLUG_LEN {
+      break;
+    }
+    slug.push('-');
+    slug.push_str(&word.to_lowercase());
+  }
+
+  format!("fix/{number}{slug}")
 }
 
 /// Fetch from origin and return the remote ref for main/master.
-fn fetch_and_resolve_base(repo_dir: &PathBuf) -> Result<String> {
+fn fetch_and_resolve_base(repo_dir: &Path) -> Result<String> {
   let status = Command::new("git")
     .args(["fetch", "origin"])
     .current_dir(repo_dir)
@@ -80,23 +441,10 @@ fn fetch_and_resolve_base(repo_dir: &PathBuf) -> Result<String> {
     anyhow::bail!("git fetch origin failed");
   }
 
-  // Try origin/main first, fall back to origin/master
-  for candidate in ["origin/main", "origin/master"] {
-    let output = Command::new("git")
-      .args(["rev-parse", "--verify", candidate])
-      .current_dir(repo_dir)
-      .output()
-      .context("failed to run git rev-parse")?;
-
-    if output.status.success() {
-      return Ok(candidate.to_string());
-    }
-  }
-
-  anyhow::bail!("could not find origin/main or origin/master");
+  Ok(format!("origin/{}", git::resolve_base_in(repo_dir)?))
 }
 
-fn branch_exists(repo_dir: &PathBuf, branch: &str) -> Result<bool> {
+fn branch_exists(repo_dir: &Path, branch: &str) -> Result<bool> {
   let output = Command::new("git")
     .args(["branch", "--list", branch])
     .current_dir(repo_dir)
@@ -109,3 +457,26 @@ fn branch_exists(repo_dir: &PathBuf, branch: &str) -> Result<bool> {
 
   Ok(!output.stdout.is_empty())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn issue_branch_slugs_the_title() {
+    assert_eq!(issue_branch(123, "Null price crash"), "fix/123-null-price-crash");
+    assert_eq!(issue_branch(7, "  Can't parse `v2.0` tags!"), "fix/7-can-t-parse-v2-0-tags");
+  }
+
+  #[test]
+  fn issue_branch_drops_non_ascii_and_empty_titles() {
+    assert_eq!(issue_branch(5, "Ünïcode ✓ fix"), "fix/5-n-code-fix");
+    assert_eq!(issue_branch(9, "!!!"), "fix/9");
+  }
+
+  #[test]
+  fn issue_branch_stops_at_whole_words() {
+    let branch = issue_branch(1, "one two three four five six seven eight nine ten eleven");
+    assert_eq!(branch, "fix/1-one-two-three-four-five-six-seven-eight");
+  }
+}
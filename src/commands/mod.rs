@@ -1,11 +1,15 @@
+mod check;
 mod claude;
+mod config;
 mod deploy;
 mod fix;
 mod ship;
 mod temp_strat;
 mod update;
 
+pub use check::CheckCommand;
 pub use claude::ClaudeCommand;
+pub use config::ConfigCommand;
 pub use deploy::DeployCommand;
 pub use fix::FixCommand;
 pub use ship::ShipCommand;
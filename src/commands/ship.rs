@@ -1,152 +1,572 @@
-use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::Command;
 
 use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
-use serde::Deserialize;
 
-#[derive(Deserialize)]
-struct MrtConfig {
-  #[serde(default)]
-  checks: Vec<Check>,
-}
+use crate::check_report::ReportFormat;
+use crate::check_runner;
+use crate::check_runner::FixMode;
+use crate::check_runner::RunOptions;
+use crate::checks;
+use crate::checks::Check;
+use crate::checks::CheckOutcome;
+use crate::ci_watch;
+use crate::config;
+use crate::config::MrtConfig;
+use crate::config::ShipConfig;
+use crate::forge;
+use crate::forge::Forge;
+use crate::forge::MergeMethod;
+use crate::forge::NewPr;
+use crate::git;
+use crate::journal::Journal;
+use crate::journal::Step;
+use crate::name_generator::generate_name;
+use crate::pr_description;
+use crate::pr_description::IssueLink;
+use crate::secrets;
+use crate::staging;
+use crate::worktree_meta::WorktreeMeta;
+
+/// Stands in for the branch name a real ship would generate.
+const GENERATED_BRANCH: &str = "<generated-name>";
 
-#[derive(Deserialize)]
-struct Check {
-  name: String,
-  command: String,
+/// PR settings with CLI flags applied on top of the `[ship]` table.
+struct PrOptions {
+  draft: bool,
+  reviewers: Vec<String>,
+  labels: Vec<String>,
+  assignees: Vec<String>,
+  auto_merge: bool,
+  merge_method: MergeMethod,
 }
 
 /// Commit, push, and open a PR for the current branch
 #[derive(Parser)]
 pub struct ShipCommand {
   /// Commit message
-  pub message: String,
+  #[arg(required_unless_present_any = ["resume", "continue_rebase"])]
+  pub message: Option<String>,
+  /// Run checks and print what ship would do, without staging, committing or pushing
+  #[arg(long)]
+  pub dry_run: bool,
+  /// Skip file and hunk selection and stage everything but `.mrt.local.toml` with `git add .`
+  #[arg(long)]
+  pub all: bool,
+  /// Continue an interrupted ship from the first step that didn't finish
+  #[arg(long, conflicts_with_all = ["message", "dry_run"])]
+  pub resume: bool,
+  /// Open the PR as a draft
+  #[arg(long)]
+  pub draft: bool,
+  /// Request a review from this user or team (repeatable)
+  #[arg(long = "reviewer", value_name = "REVIEWER")]
+  pub reviewers: Vec<String>,
+  /// Add a label to the PR (repeatable)
+  #[arg(long = "label", value_name = "LABEL")]
+  pub labels: Vec<String>,
+  /// Assign the PR to this user (repeatable)
+  #[arg(long = "assignee", value_name = "ASSIGNEE")]
+  pub assignees: Vec<String>,
+  /// Enable auto-merge even if `.mrt.toml` turns it off
+  #[arg(long, overrides_with = "no_auto_merge")]
+  pub auto_merge: bool,
+  /// Don't enable auto-merge on the PR
+  #[arg(long)]
+  pub no_auto_merge: bool,
+  /// How the PR gets merged once auto-merge kicks in
+  #[arg(long, value_enum)]
+  pub merge_method: Option<MergeMethod>,
+  /// Follow the PR's CI checks until it merges, failing if a required check fails
+  #[arg(long, conflicts_with = "dry_run")]
+  pub watch: bool,
+  /// Commit even if the staged changes look like they contain secrets
+  #[arg(long)]
+  pub allow_secrets: bool,
+  /// Branch to ship on when starting from the base branch or a detached HEAD
+  #[arg(long, conflicts_with = "resume")]
+  pub branch: Option<String>,
+  /// Fetch and rebase onto the latest base before pushing
+  #[arg(long)]
+  pub rebase: bool,
+  /// Finish a ship whose rebase stopped on conflicts
+  #[arg(long = "continue", conflicts_with_all = ["message", "dry_run"])]
+  pub continue_rebase: bool,
+  /// Run every check even if it already passed against the same content
+  #[arg(long)]
+  pub no_cache: bool,
+  /// Run the `fix` command of failing checks without asking
+  #[arg(long)]
+  pub autofix: bool,
+  /// Write a report of the check results to this file
+  #[arg(long, value_name = "PATH")]
+  pub report: Option<PathBuf>,
+  /// Report format; defaults to JUnit XML for `.xml` paths and JSON otherwise
+  #[arg(long, value_enum, requires = "report")]
+  pub report_format: Option<ReportFormat>,
 }
 
 impl ShipCommand {
   pub fn execute(self) -> Result<()> {
-    // Run pre-ship checks from .mrt.toml if present
-    if let Ok(contents) = fs::read_to_string(".mrt.toml") {
-      let config: MrtConfig = toml::from_str(&contents).context("failed to parse .mrt.toml")?;
-
-      for check in &config.checks {
-        println!("running check: {}", check.name);
-        let output = Command::new("sh")
-          .args(["-c", &check.command])
-          .output()
-          .with_context(|| format!("failed to run check '{}'", check.name))?;
-
-        if !output.status.success() {
-          let stdout = String::from_utf8_lossy(&output.stdout);
-          let stderr = String::from_utf8_lossy(&output.stderr);
-          if !stdout.is_empty() {
-            eprintln!("{stdout}");
-          }
-          if !stderr.is_empty() {
-            eprintln!("{stderr}");
+    let config = MrtConfig::load_strict()?;
+    let options = self.pr_options(&config.ship);
+    let forge = forge::detect(config.ship.forge)?;
+
+    if self.dry_run {
+      let branch = self.feature_branch(true)?;
+      let outcomes = self.run_checks(&config.checks, &branch)?;
+      let message = self.message.as_deref().unwrap_or_default();
+      return print_plan(message, &branch, &outcomes, &options, forge.as_ref());
+    }
+
+    let mut journal = match (self.resume || self.continue_rebase, Journal::load()?) {
+      (true, Some(journal)) => journal,
+      (true, None) => anyhow::bail!("no unfinished ship to resume"),
+      (false, Some(journal)) if journal.is_done(Step::Commit) => anyhow::bail!(
+        "a previous ship already committed \"{}\"; run `mrt ship --resume` to finish it",
+        journal.message
+      ),
+      (false, _) => {
+        self.feature_branch(false)?;
+        Journal::start(self.message.as_deref().unwrap_or_default())?
+      },
+    };
+
+    if !journal.is_done(Step::Checks) {
+      let outcomes = self.run_checks(&config.checks, &git::current_branch()?)?;
+      journal.record_checks(&outcomes);
+      journal.finish(Step::Checks)?;
+    }
+
+    if !journal.is_done(Step::Add) {
+      if self.all {
+        review_and_add_all()?;
+      } else {
+        staging::select()?;
+      }
+      journal.finish(Step::Add)?;
+    }
+
+    if !journal.is_done(Step::Commit) {
+      let findings = secrets::scan_staged(&config.secrets)?;
+      if !findings.is_empty() {
+        eprintln!("possible secrets in staged changes:");
+        for finding in &findings {
+          match finding.line {
+            Some(line) => eprintln!("  {}:{line}  {}", finding.path, finding.kind),
+            None => eprintln!("  {}  {}", finding.path, finding.kind),
           }
-          anyhow::bail!("check '{}' failed", check.name);
         }
+
+        if !self.allow_secrets {
+          anyhow::bail!(
+            "refusing to commit possible secrets; unstage them, allowlist them in [secrets] of \
+             .mrt.toml, or pass --allow-secrets"
+          );
+        }
+      }
+
+      let status = Command::new("git")
+        .args(["commit", "-m", &journal.message])
+        .status()
+        .context("failed to run git commit")?;
+
+      if !status.success() {
+        anyhow::bail!("git commit failed (pre-commit hook rejected?)");
       }
+
+      journal.branch = Some(git::current_branch()?);
+      journal.finish(Step::Commit)?;
     }
 
-    // Show diff for review
-    Command::new("git").args(["status"]).status().context("failed to run git status")?;
+    let rebase = self.rebase || config.ship.rebase || journal.rebased_from.is_some();
+    if rebase && !journal.is_done(Step::Rebase) {
+      self.rebase_onto_base(&mut journal, &config.checks)?;
+      journal.finish(Step::Rebase)?;
+    }
 
-    // wait for enter key to move on:
-    println!("Enter to continue...");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    let branch = git::current_branch()?;
+    if let Some(shipped) = &journal.branch
+      && *shipped != branch
+    {
+      anyhow::bail!("ship was started on {shipped}, but {branch} is checked out");
+    }
 
-    Command::new("git").args(["diff"]).status().context("failed to run git diff")?;
-    println!("Enter to continue...");
-    std::io::stdin().read_line(&mut input)?;
+    if !journal.is_done(Step::Push) {
+      let status = Command::new("git")
+        .args(["push", "-u", "origin", &branch])
+        .status()
+        .context("failed to run git push")?;
 
-    // Stage all changes
-    let status =
-      Command::new("git").args(["add", "."]).status().context("failed to run git add")?;
+      if !status.success() {
+        anyhow::bail!("git push failed");
+      }
 
-    if !status.success() {
-      anyhow::bail!("git add failed");
+      journal.finish(Step::Push)?;
     }
 
-    // Commit
-    let status = Command::new("git")
-      .args(["commit", "-m", &self.message])
-      .status()
-      .context("failed to run git commit")?;
+    if !journal.is_done(Step::Pr) {
+      // Detect base branch (main or master)
+      let base = git::resolve_base()?;
 
-    if !status.success() {
-      anyhow::bail!("git commit failed (pre-commit hook rejected?)");
+      let pr_url = match forge.find_pr(&branch)? {
+        Some(url) => {
+          println!("using existing PR");
+          url
+        },
+        None => create_pr(forge.as_ref(), &journal, &branch, &base, &options)?,
+      };
+
+      journal.pr_url = Some(pr_url);
+      journal.finish(Step::Pr)?;
     }
 
-    // Detect current branch
-    let branch_output = Command::new("git")
-      .args(["rev-parse", "--abbrev-ref", "HEAD"])
-      .output()
-      .context("failed to detect current branch")?;
+    if options.auto_merge && !journal.is_done(Step::AutoMerge) {
+      let pr_url = journal.pr_url.as_deref().unwrap_or_default();
+      match forge.enable_auto_merge(pr_url, options.merge_method) {
+        Ok(()) => journal.auto_merge_enabled = true,
+        Err(e) => eprintln!("warning: could not enable auto-merge: {e}"),
+      }
 
-    if !branch_output.status.success() {
-      anyhow::bail!("failed to detect current branch");
+      journal.finish(Step::AutoMerge)?;
     }
 
-    let branch = String::from_utf8_lossy(&branch_output.stdout).trim().to_string();
+    let pr_url = journal.pr_url.clone().unwrap_or_default();
+    let auto_merge_enabled = journal.auto_merge_enabled;
+    println!("\n{pr_url}");
+    journal.clear()?;
 
-    // Push
-    let status = Command::new("git")
-      .args(["push", "-u", "origin", &branch])
-      .status()
-      .context("failed to run git push")?;
+    // Waiting for a merge that auto-merge won't make would never end.
+    if self.watch {
+      ci_watch::watch(forge.as_ref(), &pr_url, auto_merge_enabled)?;
+    }
 
-    if !status.success() {
-      anyhow::bail!("git push failed");
+    Ok(())
+  }
+
+  /// Run the checks through the shared runner, offering fixes unless this is a dry run.
+  fn run_checks(&self, checks: &[Check], branch: &str) -> Result<Vec<CheckOutcome>> {
+    let fix = match (self.dry_run, self.autofix) {
+      (true, _) => FixMode::Never,
+      (false, true) => FixMode::Always,
+      (false, false) => FixMode::Ask,
+    };
+    let report = self
+      .report
+      .as_deref()
+      .map(|path| (path, self.report_format.unwrap_or_else(|| ReportFormat::from_path(path))));
+
+    check_runner::run(
+      checks,
+      branch,
+      &RunOptions {
+        names: &[],
+        changed: None,
+        use_cache: !self.no_cache,
+        record: !self.dry_run,
+        stream: false,
+        fix,
+        report,
+      },
+    )
+  }
+
+  /// Rebase the shipped commit onto the freshly fetched base, then re-run the checks that the
+  /// incoming base changes touch. Conflicts stop the ship until `mrt ship --continue`.
+  fn rebase_onto_base(&self, journal: &mut Journal, checks: &[Check]) -> Result<()> {
+    let conflict_help = "resolve the conflicts, `git add` the files, then run `mrt ship \
+                         --continue` (or `git rebase --abort` to give up on rebasing)";
+
+    match &journal.rebased_from {
+      None => {
+        let base = git::resolve_base()?;
+        let status = Command::new("git")
+          .args(["fetch", "origin", &base])
+          .status()
+          .context("failed to run git fetch")?;
+
+        if !status.success() {
+          anyhow::bail!("git fetch origin {base} failed");
+        }
+
+        journal.rebased_from = Some(git::stdout(&["rev-parse", "HEAD"])?.trim().to_string());
+        journal.save()?;
+
+        let status = Command::new("git")
+          .args(["rebase", &format!("origin/{base}")])
+          .status()
+          .context("failed to run git rebase")?;
+
+        if !status.success() {
+          anyhow::bail!("rebase onto origin/{base} stopped on conflicts; {conflict_help}");
+        }
+      },
+      Some(_) if rebase_in_progress()? => {
+        if !self.continue_rebase {
+          anyhow::bail!("a rebase is still in progress; {conflict_help}");
+        }
+
+        let status = Command::new("git")
+          .args(["rebase", "--continue"])
+          .env("GIT_EDITOR", "true")
+          .status()
+          .context("failed to run git rebase --continue")?;
+
+        if !status.success() {
+          anyhow::bail!("rebase stopped again; {conflict_help}");
+        }
+      },
+      Some(_) => {},
     }
 
-    // Detect base branch (main or master)
-    let base = resolve_base()?;
+    let from = journal.rebased_from.as_deref().unwrap_or("HEAD");
+    let incoming = git::stdout(&["diff", "--name-only", "--no-renames", from, "HEAD"])?;
+    if incoming.trim().is_empty() {
+      return Ok(());
+    }
 
-    // Create PR
-    let pr_output = Command::new("gh")
-      .args(["pr", "create", "--fill", "--base", &base])
-      .output()
-      .context("failed to run gh pr create")?;
+    let branch = journal.branch.clone().unwrap_or(git::current_branch()?);
+    let changed: Vec<String> = incoming.lines().map(str::to_string).collect();
+    let (selected, _) = checks::select(checks, &branch, &changed)?;
+    if !selected.is_empty() {
+      println!("re-running checks affected by the rebase");
+      let outcomes = checks::run(&selected, false)?;
+      checks::ensure_passed(&outcomes, false)?;
+    }
+
+    Ok(())
+  }
 
-    if !pr_output.status.success() {
-      let stderr = String::from_utf8_lossy(&pr_output.stderr);
-      anyhow::bail!("gh pr create failed: {stderr}");
+  /// The branch to ship from. Never the base branch or a detached HEAD: those get a new branch,
+  /// from `--branch` or a generated name, that takes the uncommitted changes and any local
+  /// commits along. With `dry_run`, only reports what would happen, and a name still to be
+  /// generated comes back as [`GENERATED_BRANCH`].
+  fn feature_branch(&self, dry_run: bool) -> Result<String> {
+    let current = git::current_branch()?;
+    let base = git::resolve_base()?;
+    let stranded = current == base || current == "HEAD";
+
+    if !stranded {
+      return match &self.branch {
+        Some(name) if *name != current => anyhow::bail!(
+          "already on {current}; --branch only applies when shipping from {base} or a detached HEAD"
+        ),
+        _ => Ok(current),
+      };
     }
 
-    let pr_url = String::from_utf8_lossy(&pr_output.stdout).trim().to_string();
+    let name = match &self.branch {
+      Some(name) => name.clone(),
+      // The real run draws its own name, so any drawn here would be wrong.
+      None if dry_run => {
+        println!("would create a branch with a generated name (currently on {current})");
+        return Ok(GENERATED_BRANCH.to_string());
+      },
+      None => loop {
+        let candidate = generate_name();
+        if !branch_exists(&candidate)? {
+          break candidate;
+        }
+      },
+    };
 
-    // Enable auto-merge
-    let status = Command::new("gh")
-      .args(["pr", "merge", "--auto", "--squash"])
+    if dry_run {
+      println!("would create branch {name} (currently on {current})");
+      return Ok(name);
+    }
+
+    if branch_exists(&name)? {
+      anyhow::bail!("branch {name} already exists");
+    }
+
+    let status = Command::new("git")
+      .args(["switch", "-c", &name])
       .status()
-      .context("failed to run gh pr merge --auto")?;
+      .context("failed to run git switch")?;
 
     if !status.success() {
-      eprintln!("warning: could not enable auto-merge");
+      anyhow::bail!("git switch -c {name} failed");
     }
 
-    println!("\n{pr_url}");
+    // Local commits on the base now live on the new branch, so put the base back where origin
+    // has it rather than leaving them to be pushed from there later.
+    if current == base {
+      let upstream = format!("origin/{base}");
+      let ahead = git::stdout(&["rev-list", "--count", &format!("{upstream}..{base}")])?;
+      let ahead = ahead.trim();
+      if ahead != "0" {
+        git::stdout(&["branch", "-f", &base, &upstream])?;
+        println!("moved {ahead} local commit(s) from {base} onto {name}");
+      }
+    }
 
-    Ok(())
+    println!("shipping from new branch {name}");
+    Ok(name)
+  }
+
+  fn pr_options(&self, config: &ShipConfig) -> PrOptions {
+    let merged = |cli: &[String], config: &[String]| -> Vec<String> {
+      let mut values = config.to_vec();
+      values.extend(cli.iter().filter(|v| !config.contains(v)).cloned());
+      values
+    };
+
+    let auto_merge = match (self.auto_merge, self.no_auto_merge) {
+      (true, _) => true,
+      (_, true) => false,
+      _ => config.auto_merge.unwrap_or(true),
+    };
+
+    PrOptions {
+      draft: self.draft || config.draft,
+      reviewers: merged(&self.reviewers, &config.reviewers),
+      labels: merged(&self.labels, &config.labels),
+      assignees: merged(&self.assignees, &config.assignees),
+      auto_merge,
+      merge_method: self.merge_method.or(config.merge_method).unwrap_or_default(),
+    }
   }
 }
 
-fn resolve_base() -> Result<String> {
-  for candidate in ["main", "master"] {
-    let output = Command::new("git")
-      .args(["rev-parse", "--verify", &format!("origin/{candidate}")])
-      .output()
-      .context("failed to run git rev-parse")?;
+fn create_pr(
+  forge: &dyn Forge, journal: &Journal, branch: &str, base: &str, options: &PrOptions,
+) -> Result<String> {
+  let diffstat = git::stdout(&["diff", "--stat", &format!("origin/{base}...HEAD")])?;
+  let issue = linked_issue(&journal.message, branch)?;
+  let outcomes = journal.check_outcomes();
+  let pr = pr_description::build(&journal.message, &diffstat, &outcomes, issue.as_ref());
+
+  forge.create_pr(&NewPr {
+    title: &pr.title,
+    body: &pr.body,
+    base,
+    head: branch,
+    draft: options.draft,
+    reviewers: &options.reviewers,
+    labels: &options.labels,
+    assignees: &options.assignees,
+  })
+}
+
+/// The issue to mention in the PR: the one `mrt fix --issue` linked to this worktree, which the
+/// PR closes, or else one the message or branch name refers to.
+fn linked_issue(message: &str, branch: &str) -> Result<Option<IssueLink>> {
+  let linked = WorktreeMeta::load()?.and_then(|meta| meta.issue);
+  let link = match linked {
+    Some(issue) => Some(IssueLink::Closes(issue)),
+    None => pr_description::issue_reference(message, branch).map(IssueLink::Refs),
+  };
+  Ok(link)
+}
+
+/// Show status and diff for review, then stage everything.
+fn review_and_add_all() -> Result<()> {
+  Command::new("git").args(["status"]).status().context("failed to run git status")?;
 
-    if output.status.success() {
-      return Ok(candidate.to_string());
+  // wait for enter key to move on:
+  println!("Enter to continue...");
+  let mut input = String::new();
+  std::io::stdin().read_line(&mut input)?;
+
+  Command::new("git").args(["diff"]).status().context("failed to run git diff")?;
+  println!("Enter to continue...");
+  std::io::stdin().read_line(&mut input)?;
+
+  let status = Command::new("git")
+    .args(["add", "--", "."])
+    .arg(config::local_file_exclude())
+    .status()
+    .context("failed to run git add")?;
+
+  if !status.success() {
+    anyhow::bail!("git add failed");
+  }
+
+  Ok(())
+}
+
+/// Print the files, commit, push target, PR description and forge calls a real ship would make.
+fn print_plan(
+  message: &str, branch: &str, outcomes: &[CheckOutcome], options: &PrOptions, forge: &dyn Forge,
+) -> Result<()> {
+  let output = Command::new("git")
+    .args(["add", "--dry-run", "--", "."])
+    .arg(config::local_file_exclude())
+    .output()
+    .context("failed to run git add --dry-run")?;
+
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    anyhow::bail!("git add --dry-run failed: {stderr}");
+  }
+
+  let base = git::resolve_base()?;
+
+  println!("\ndry run: nothing will be staged, committed or pushed\n");
+
+  println!("files to stage:");
+  let staged = String::from_utf8_lossy(&output.stdout);
+  if staged.trim().is_empty() {
+    println!("  (none)");
+  }
+  for line in staged.lines() {
+    println!("  {line}");
+  }
+
+  println!("\ncommit message:\n  {message}");
+  println!("\npush: origin {branch}");
+  println!("base: {base}");
+  // Nothing is committed yet, so the diffstat covers the working tree against the base.
+  let merge_base = git::stdout(&["merge-base", &format!("origin/{base}"), "HEAD"])?;
+  let diffstat = git::stdout(&["diff", "--stat", merge_base.trim()])?;
+  let issue = linked_issue(message, branch)?;
+  let pr = pr_description::build(message, &diffstat, outcomes, issue.as_ref());
+
+  println!("\npr title:\n  {}", pr.title);
+  println!("\npr body:");
+  for line in pr.body.lines() {
+    println!("  {line}");
+  }
+
+  let pr = NewPr {
+    title: &pr.title,
+    body: &pr.body,
+    base: &base,
+    head: branch,
+    draft: options.draft,
+    reviewers: &options.reviewers,
+    labels: &options.labels,
+    assignees: &options.assignees,
+  };
+  println!("\n{}:", forge.name());
+  for call in forge.describe_create_pr(&pr) {
+    println!("  {call}");
+  }
+  if options.auto_merge {
+    println!("  {}", forge.describe_auto_merge(options.merge_method));
+  }
+
+  Ok(())
+}
+
+fn rebase_in_progress() -> Result<bool> {
+  for dir in ["rebase-merge", "rebase-apply"] {
+    let path = git::stdout(&["rev-parse", "--git-path", dir])?;
+    if Path::new(path.trim()).exists() {
+      return Ok(true);
     }
   }
+  Ok(false)
+}
 
-  anyhow::bail!("could not find origin/main or origin/master");
+fn branch_exists(name: &str) -> Result<bool> {
+  let status = Command::new("git")
+    .args(["rev-parse", "--verify", "--quiet", &format!("refs/heads/{name}")])
+    .stdout(std::process::Stdio::null())
+    .status()
+    .context("failed to run git rev-parse")?;
+  Ok(status.success())
 }
@@ -1,5 +1,4 @@
 use std::fs;
-use std::path::PathBuf;
 use std::process::Command;
 use std::process::Stdio;
 
@@ -7,27 +6,23 @@ use anyhow::Context;
 use anyhow::Result;
 use clap::Parser;
 
+use crate::config::MrtConfig;
 use crate::name_generator::generate_name;
 use crate::window;
+use crate::workspace;
 
 const CARGO_TEMPLATE: &str = include_str!("../../templates/Cargo.toml.template");
 const MAIN_TEMPLATE: &str = include_str!("../../templates/main.rs.template");
 const RUSTFMT_TEMPLATE: &str = include_str!("../../rustfmt.toml");
 
-const SS151_DEPS: &[&[&str]] = &[
-  &["pdq", "--registry", "ss151"],
-  &["lots", "--registry", "ss151", "--features", "equities"],
-  &["agg-stats", "--registry", "ss151"],
-  &["feature-data", "--registry", "ss151"],
-];
-
 /// Generate a new temporary strategy crate
 #[derive(Parser)]
 pub struct TempStratCommand;
 
 impl TempStratCommand {
   pub fn execute(self) -> Result<()> {
-    let base = temp_strats_dir();
+    let config = MrtConfig::load()?;
+    let base = workspace::primary_root(&config)?.join("temp-strats");
     fs::create_dir_all(&base)?;
 
     let name = loop {
@@ -49,19 +44,19 @@ impl TempStratCommand {
 
     println!("{}", dir.display());
 
-    Command::new("code").arg(&dir).arg(src_dir.join("main.rs")).spawn()?;
-
-    let _ = window::snap_window_left(&name);
+    if let Some(window_title) = config.editor.open(&dir, Some(&src_dir.join("main.rs")))? {
+      let _ = window::snap_window_left(&window_title);
+    }
 
-    for dep in SS151_DEPS {
+    for dep in &config.temp_strat.deps {
       let status = Command::new("cargo")
         .arg("add")
-        .args(*dep)
+        .args(dep)
         .current_dir(&dir)
         .status()
-        .with_context(|| format!("failed to run cargo add {}", dep[0]))?;
+        .with_context(|| format!("failed to run cargo add {}", dep.join(" ")))?;
       if !status.success() {
-        anyhow::bail!("cargo add {} failed", dep[0]);
+        anyhow::bail!("cargo add {} failed", dep.join(" "));
       }
     }
 
@@ -77,8 +72,3 @@ impl TempStratCommand {
     Ok(())
   }
 }
-
-fn temp_strats_dir() -> PathBuf {
-  let home = std::env::var("HOME").expect("HOME not set");
-  PathBuf::from(home).join("projects/temp-strats")
-}
@@ -3,14 +3,16 @@ use std::process::Command;
 use anyhow::{Context, Result};
 use clap::Parser;
 
+use crate::config::MrtConfig;
+use crate::workspace;
+
 /// Rebuild and reinstall mrt from source
 #[derive(Parser)]
 pub struct UpdateCommand;
 
 impl UpdateCommand {
     pub fn execute(self) -> Result<()> {
-        let home = std::env::var("HOME")?;
-        let mrt_dir = format!("{home}/projects/mrt");
+        let mrt_dir = workspace::find_repo(&MrtConfig::load()?, "mrt")?;
 
         let status = Command::new("cargo")
             .args(["install", "--path", "."])
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+use toml::Table;
+use toml::Value;
+
+use crate::checks::Check;
+use crate::config_schema;
+use crate::config_schema::Problem;
+use crate::editor::EditorConfig;
+use crate::forge::ForgeKind;
+use crate::forge::MergeMethod;
+use crate::git;
+use crate::secrets::SecretsConfig;
+
+const REPO_FILE: &str = ".mrt.toml";
+const LOCAL_FILE: &str = ".mrt.local.toml";
+
+/// A pathspec leaving every `.mrt.local.toml` out of a `git add`, so personal overrides never get
+/// committed.
+pub fn local_file_exclude() -> String {
+  format!(":(exclude,glob)**/{LOCAL_FILE}")
+}
+
+/// mrt's settings, merged from every config layer.
+#[derive(Serialize, Deserialize)]
+pub struct MrtConfig {
+  /// Directories repos live under, searched in order; new ones go in the first
+  #[serde(default)]
+  pub projects: Vec<String>,
+  #[serde(default)]
+  pub checks: Vec<Check>,
+  #[serde(default)]
+  pub ship: ShipConfig,
+  #[serde(default)]
+  pub secrets: SecretsConfig,
+  /// Deploy targets by name, as used by `mrt deploy <target>`
+  #[serde(default)]
+  pub deploy: BTreeMap<String, DeployTarget>,
+  #[serde(default)]
+  pub temp_strat: TempStratConfig,
+  #[serde(default)]
+  pub editor: EditorConfig,
+}
+
+impl Default for MrtConfig {
+  fn default() -> Self {
+    let pdq_studio = DeployTarget {
+      host: "krjr84".to_string(),
+      command: "sudo -iu lewis bash -lc 'cd ~/pdq-studio && git pull && bun run build' && sudo \
+                systemctl restart pdq-studio"
+        .to_string(),
+    };
+
+    MrtConfig {
+      projects: vec!["~/projects".to_string()],
+      checks: Vec::new(),
+      ship: ShipConfig::default(),
+      secrets: SecretsConfig::default(),
+      deploy: BTreeMap::from([("pdq-studio".to_string(), pdq_studio)]),
+      temp_strat: TempStratConfig::default(),
+      editor: EditorConfig::default(),
+    }
+  }
+}
+
+impl MrtConfig {
+  /// Merge the config layers over the built-in defaults, warning about keys that don't fit the
+  /// schema.
+  pub fn load() -> Result<Self> {
+    Self::load_in(Path::new("."))
+  }
+
+  /// Like [`MrtConfig::load`], for the repo at `dir`.
+  pub fn load_in(dir: &Path) -> Result<Self> {
+    Self::load_checked(dir, &[])
+  }
+
+  /// Like [`MrtConfig::load`], but failing on problems under `checks` or `ship`, where a
+  /// mistyped key would quietly change which checks run or how the PR is opened.
+  pub fn load_strict() -> Result<Self> {
+    Self::load_checked(Path::new("."), &["checks", "ship"])
+  }
+
+  fn load_checked(dir: &Path, fatal: &[&str]) -> Result<Self> {
+    let layers = Layers::load_in(dir)?;
+    let mut errors = 0;
+    for layer in &layers.0 {
+      for problem in layer.problems() {
+        let section = problem.key.split(['.', '[']).next().unwrap_or_default();
+        let level = match fatal.contains(&section) {
+          true => "error",
+          false => "warning",
+        };
+        eprintln!("{level}: {}: `{}` {}", layer.label(), problem.key, problem.message);
+        errors += usize::from(level == "error");
+      }
+    }
+
+    if errors > 0 {
+      anyhow::bail!("{errors} config problem(s) under [ship] or [[checks]]; fix them first");
+    }
+    Self::from_layers(&layers)
+  }
+
+  pub fn from_layers(layers: &Layers) -> Result<Self> {
+    let (merged, _) = layers.merge();
+    Value::Table(merged).try_into().context("invalid mrt config")
+  }
+}
+
+/// The `[ship]` table.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ShipConfig {
+  #[serde(default)]
+  pub draft: bool,
+  #[serde(default)]
+  pub rebase: bool,
+  #[serde(default)]
+  pub reviewers: Vec<String>,
+  #[serde(default)]
+  pub labels: Vec<String>,
+  #[serde(default)]
+  pub assignees: Vec<String>,
+  #[serde(default)]
+  pub auto_merge: Option<bool>,
+  #[serde(default)]
+  pub merge_method: Option<MergeMethod>,
+  /// Forge to use instead of detecting it from the origin URL
+  #[serde(default)]
+  pub forge: Option<ForgeKind>,
+}
+
+/// A `[deploy.<target>]` table: the host to ssh into and the command to run there.
+#[derive(Serialize, Deserialize)]
+pub struct DeployTarget {
+  pub host: String,
+  pub command: String,
+}
+
+/// The `[temp_strat]` table.
+#[derive(Serialize, Deserialize)]
+pub struct TempStratConfig {
+  /// `cargo add` arguments for each dependency of a new temp strat
+  pub deps: Vec<Vec<String>>,
+}
+
+impl Default for TempStratConfig {
+  fn default() -> Self {
+    let deps: &[&[&str]] = &[
+      &["pdq", "--registry", "ss151"],
+      &["lots", "--registry", "ss151", "--features", "equities"],
+      &["agg-stats", "--registry", "ss151"],
+      &["feature-data", "--registry", "ss151"],
+    ];
+
+    TempStratConfig {
+      deps: deps.iter().map(|dep| dep.iter().map(|a| a.to_string()).collect()).collect(),
+    }
+  }
+}
+
+/// One source of settings. `path` is `None` for the built-in defaults.
+pub struct Layer {
+  pub path: Option<PathBuf>,
+  table: Table,
+}
+
+impl Layer {
+  /// `~`-abbreviated path of the file, or `default`.
+  pub fn label(&self) -> String {
+    let Some(path) = &self.path else {
+      return "default".to_string();
+    };
+
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+    match home.as_deref().and_then(|home| path.strip_prefix(home).ok()) {
+      Some(rest) => format!("~/{}", rest.display()),
+      None => path.display().to_string(),
+    }
+  }
+
+  /// Keys that don't fit the schema. The built-in defaults always do.
+  pub fn problems(&self) -> Vec<Problem> {
+    match self.path {
+      Some(_) => config_schema::validate(&Value::Table(self.table.clone())),
+      None => Vec::new(),
+    }
+  }
+}
+
+/// The config layers in precedence order, lowest first:
+///
+/// 1. built-in defaults
+/// 2. `~/.config/mrt/config.toml` (or `$XDG_CONFIG_HOME/mrt/config.toml`)
+/// 3. the repo's `.mrt.toml`, found by walking up from the current directory to the git root
+/// 4. `.mrt.local.toml` beside it, for untracked personal overrides
+///
+/// Tables merge key by key. Any other value, including arrays such as `checks`, is replaced
+/// whole by the layer above.
+pub struct Layers(pub Vec<Layer>);
+
+impl Layers {
+  pub fn load() -> Result<Self> {
+    Self::load_in(Path::new("."))
+  }
+
+  /// The layers that apply in `dir`, whose repo config is searched for from there.
+  pub fn load_in(dir: &Path) -> Result<Self> {
+    let defaults = Value::try_from(MrtConfig::default()).context("failed to encode defaults")?;
+    let Value::Table(defaults) = defaults else {
+      anyhow::bail!("defaults did not encode as a table");
+    };
+
+    let mut layers = vec![Layer { path: None, table: defaults }];
+
+    let mut paths = Vec::new();
+    paths.extend(global_path());
+    if let Some(dir) = repo_config_dir(dir)? {
+      paths.push(dir.join(REPO_FILE));
+      paths.push(dir.join(LOCAL_FILE));
+    }
+
+    for path in paths {
+      let Ok(contents) = fs::read_to_string(&path) else {
+        continue;
+      };
+      let table = toml::from_str(&contents)
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+      layers.push(Layer { path: Some(path), table });
+    }
+
+    Ok(Layers(layers))
+  }
+
+  /// Merge every layer, returning the result and, for each dotted key path set by a layer, the
+  /// index of the layer its value came from.
+  pub fn merge(&self) -> (Table, BTreeMap<String, usize>) {
+    let mut merged = Table::new();
+    let mut sources = BTreeMap::new();
+
+    for (index, layer) in self.0.iter().enumerate() {
+      merge_into(&mut merged, &layer.table, index, "", &mut sources);
+    }
+
+    (merged, sources)
+  }
+}
+
+fn merge_into(
+  base: &mut Table, layer: &Table, index: usize, prefix: &str,
+  sources: &mut BTreeMap<String, usize>,
+) {
+  for (key, value) in layer {
+    let path = match prefix {
+      "" => key.clone(),
+      prefix => format!("{prefix}.{key}"),
+    };
+
+    match value {
+      Value::Table(table) => {
+        if !matches!(base.get(key), Some(Value::Table(_))) {
+          base.insert(key.clone(), Value::Table(Table::new()));
+        }
+        if let Some(Value::Table(base)) = base.get_mut(key) {
+          merge_into(base, table, index, &path, sources);
+        }
+      },
+      value => {
+        let nested = format!("{path}.");
+        sources.retain(|k, _| !k.starts_with(&nested));
+        base.insert(key.clone(), value.clone());
+        sources.insert(path, index);
+      },
+    }
+  }
+}
+
+/// `$XDG_CONFIG_HOME/mrt/config.toml`, falling back to `~/.config/mrt/config.toml`.
+fn global_path() -> Option<PathBuf> {
+  let config_home = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+  Some(config_home.join("mrt").join("config.toml"))
+}
+
+/// The nearest directory holding `.mrt.toml` or `.mrt.local.toml`, searching from `start` up to
+/// the git root. Outside a git repo only `start` itself is searched.
+fn repo_config_dir(start: &Path) -> Result<Option<PathBuf>> {
+  let cwd = std::env::current_dir().context("failed to read the current directory")?.join(start);
+  let root =
+    git::stdout_in(start, &["rev-parse", "--show-toplevel"]).ok().map(|r| PathBuf::from(r.trim()));
+
+  let has_config = |dir: &Path| dir.join(REPO_FILE).exists() || dir.join(LOCAL_FILE).exists();
+
+  for dir in cwd.ancestors() {
+    if has_config(dir) {
+      return Ok(Some(dir.to_path_buf()));
+    }
+    if root.as_deref().is_none_or(|root| dir == root) {
+      break;
+    }
+  }
+
+  Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn layer(toml: &str) -> Layer {
+    Layer { path: Some(PathBuf::from("layer.toml")), table: toml::from_str(toml).unwrap() }
+  }
+
+  #[test]
+  fn merge_combines_tables_key_by_key() {
+    let layers = Layers(vec![
+      layer("[ship]\ndraft = true\nreviewers = [\"ana\"]"),
+      layer("[ship]\nrebase = true\nreviewers = [\"bo\"]"),
+    ]);
+    let (merged, sources) = layers.merge();
+
+    let expected: Table =
+      toml::from_str("[ship]\ndraft = true\nrebase = true\nreviewers = [\"bo\"]").unwrap();
+    assert_eq!(merged, expected);
+    assert_eq!(sources["ship.draft"], 0);
+    assert_eq!(sources["ship.rebase"], 1);
+    assert_eq!(sources["ship.reviewers"], 1);
+  }
+
+  #[test]
+  fn merge_replaces_arrays_of_tables_whole() {
+    let layers = Layers(vec![
+      layer("[[checks]]\nname = \"fmt\"\ncommand = \"cargo fmt --check\""),
+      layer("[[checks]]\nname = \"test\"\ncommand = \"cargo test\""),
+    ]);
+    let (merged, sources) = layers.merge();
+
+    let checks = merged["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0]["name"].as_str(), Some("test"));
+    assert_eq!(sources["checks"], 1);
+  }
+
+  #[test]
+  fn merge_lets_a_table_replace_a_scalar_and_forgets_its_nested_sources() {
+    let layers =
+      Layers(vec![layer("[deploy.web]\nhost = \"a\"\ncommand = \"b\""), layer("deploy = 1")]);
+    let (merged, sources) = layers.merge();
+
+    assert_eq!(merged["deploy"].as_integer(), Some(1));
+    assert_eq!(sources.keys().collect::<Vec<_>>(), ["deploy"]);
+  }
+
+  #[test]
+  fn defaults_deserialize_back_into_a_config() {
+    let defaults = Value::try_from(MrtConfig::default()).unwrap();
+    let Value::Table(table) = defaults else { panic!("defaults are not a table") };
+    let config = MrtConfig::from_layers(&Layers(vec![Layer { path: None, table }])).unwrap();
+    assert_eq!(config.projects, ["~/projects"]);
+    assert!(config.deploy.contains_key("pdq-studio"));
+  }
+}
@@ -0,0 +1,324 @@
+use serde_json::Value as Json;
+use serde_json::json;
+use toml::Value;
+
+/// A key in a config file that doesn't fit the schema.
+pub struct Problem {
+  pub key: String,
+  pub message: String,
+}
+
+/// JSON Schema for mrt's config files, for editor completion and `mrt config validate`.
+///
+/// Every file is a partial layer, so only checks, which replace each other whole, have required
+/// keys.
+pub fn schema() -> Json {
+  let strings = json!({ "type": "array", "items": { "type": "string" } });
+
+  let check = json!({
+    "type": "object",
+    "description": "A check that ship and `mrt check` run",
+    "required": ["name", "command"],
+    "additionalProperties": false,
+    "properties": {
+      "name": { "type": "string" },
+      "command": {
+        "type": "string",
+        "description": "Shell command; the check fails on a non-zero exit",
+      },
+      "timeout": {
+        "type": "integer",
+        "minimum": 0,
+        "description": "Kill the check if it runs longer than this many seconds",
+      },
+      "parallel": {
+        "type": "boolean",
+        "description": "Run at the same time as the other `parallel` checks",
+      },
+      "group": {
+        "type": "string",
+        "description": "Run at the same time as the other checks in this group",
+      },
+      "paths": {
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Only run when a changed path matches one of these globs",
+      },
+      "branches": {
+        "type": "string",
+        "description": "Only run when the current branch matches this glob",
+      },
+      "fix": {
+        "type": "string",
+        "description": "Command that repairs what this check complains about",
+      },
+    },
+  });
+
+  json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "title": "mrt config",
+    "type": "object",
+    "additionalProperties": false,
+    "properties": {
+      "projects": {
+        "type": "array",
+        "items": { "type": "string" },
+        "description": "Directories repos live under, searched in order; new ones go in the first",
+      },
+      "checks": { "type": "array", "items": check },
+      "ship": {
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+          "draft": { "type": "boolean" },
+          "rebase": {
+            "type": "boolean",
+            "description": "Rebase onto the latest base before pushing",
+          },
+          "reviewers": strings,
+          "labels": strings,
+          "assignees": strings,
+          "auto_merge": { "type": "boolean" },
+          "merge_method": { "enum": ["squash", "rebase", "merge"] },
+          "forge": {
+            "enum": ["github", "gitlab", "gitea"],
+            "description": "Forge to use instead of detecting it from the origin URL",
+          },
+        },
+      },
+      "secrets": {
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+          "allow": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Strings that are never reported, e.g. documented example keys",
+          },
+          "allow_paths": {
+            "type": "array",
+            "items": { "type": "string" },
+            "description": "Globs of paths that are never scanned",
+          },
+        },
+      },
+      "deploy": {
+        "type": "object",
+        "description": "Deploy targets by name",
+        "additionalProperties": {
+          "type": "object",
+          "additionalProperties": false,
+          "properties": {
+            "host": { "type": "string" },
+            "command": { "type": "string" },
+          },
+        },
+      },
+      "temp_strat": {
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+          "deps": {
+            "type": "array",
+            "items": strings,
+            "description": "`cargo add` arguments for each dependency",
+          },
+        },
+      },
+      "editor": {
+        "type": "object",
+        "description": "What `mrt fix` and `mrt temp-strat` open their directory in",
+        "additionalProperties": false,
+        "properties": {
+          "kind": {
+            "enum": ["vscode", "cursor", "zed", "jetbrains", "terminal", "none"],
+            "description": "`terminal` runs $EDITOR in a new terminal window",
+          },
+          "command": {
+            "type": "string",
+            "description": "Program to run instead of the kind's usual launcher",
+          },
+        },
+      },
+    },
+  })
+}
+
+/// Check a parsed config file against the schema, suggesting the closest key for unknown ones.
+pub fn validate(value: &Value) -> Vec<Problem> {
+  let mut problems = Vec::new();
+  check(value, &schema(), "", &mut problems);
+  problems
+}
+
+fn check(value: &Value, schema: &Json, key: &str, problems: &mut Vec<Problem>) {
+  let mut problem = |message: String| problems.push(Problem { key: key.to_string(), message });
+
+  if let Some(allowed) = schema["enum"].as_array() {
+    let names: Vec<&str> = allowed.iter().filter_map(Json::as_str).collect();
+    if !value.as_str().is_some_and(|v| names.contains(&v)) {
+      problem(format!("must be one of {}, found {value}", names.join(", ")));
+    }
+    return;
+  }
+
+  let expected = schema["type"].as_str().unwrap_or_default();
+  let matches = match expected {
+    "object" => value.is_table(),
+    "array" => value.is_array(),
+    "string" => value.is_str(),
+    "boolean" => value.is_bool(),
+    "integer" => value.as_integer().is_some_and(|n| n >= schema["minimum"].as_i64().unwrap_or(n)),
+    _ => true,
+  };
+  if !matches {
+    problem(format!("should be {}, found {}", describe(expected, schema), value.type_str()));
+    return;
+  }
+
+  match value {
+    Value::Array(items) => {
+      for (i, item) in items.iter().enumerate() {
+        check(item, &schema["items"], &format!("{key}[{i}]"), problems);
+      }
+    },
+    Value::Table(table) => {
+      if let Some(required) = schema["required"].as_array() {
+        for name in required.iter().filter_map(Json::as_str) {
+          if !table.contains_key(name) {
+            problem(format!("is missing `{name}`"));
+          }
+        }
+      }
+
+      let properties = schema["properties"].as_object();
+      for (name, item) in table {
+        let path = match key {
+          "" => name.clone(),
+          key => format!("{key}.{name}"),
+        };
+
+        match (properties.and_then(|p| p.get(name)), &schema["additionalProperties"]) {
+          (Some(item_schema), _) => check(item, item_schema, &path, problems),
+          (None, Json::Object(_)) => check(item, &schema["additionalProperties"], &path, problems),
+          (None, _) => {
+            let known =
+              properties.map(|p| p.keys().map(String::as_str).collect()).unwrap_or_default();
+            let message = match suggest(name, known) {
+              Some(close) => format!("is not a known key (did you mean `{close}`?)"),
+              None => "is not a known key".to_string(),
+            };
+            problems.push(Problem { key: path, message });
+          },
+        }
+      }
+    },
+    _ => {},
+  }
+}
+
+fn describe(expected: &str, schema: &Json) -> String {
+  match (expected, schema["minimum"].as_i64()) {
+    ("integer", Some(min)) => format!("an integer of at least {min}"),
+    ("integer", None) => "an integer".to_string(),
+    ("object", _) => "a table".to_string(),
+    ("array", _) => "an array".to_string(),
+    (other, _) => format!("a {other}"),
+  }
+}
+
+/// The known key most like `name`, if any is close enough to be a likely typo.
+fn suggest<'a>(name: &str, known: Vec<&'a str>) -> Option<&'a str> {
+  known
+    .into_iter()
+    .map(|k| (strsim::jaro(name, k), k))
+    .filter(|(score, _)| *score > 0.7)
+    .max_by(|a, b| a.0.total_cmp(&b.0))
+    .map(|(_, k)| k)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn problems(toml: &str) -> Vec<(String, String)> {
+    let value: Value = toml::from_str(toml).unwrap();
+    validate(&value).into_iter().map(|p| (p.key, p.message)).collect()
+  }
+
+  fn problem(key: &str, message: &str) -> (String, String) {
+    (key.to_string(), message.to_string())
+  }
+
+  #[test]
+  fn validate_accepts_a_full_config() {
+    let config = r#"
+      projects = ["~/src"]
+
+      [[checks]]
+      name = "test"
+      command = "cargo test"
+      timeout = 600
+      paths = ["src/**"]
+
+      [ship]
+      draft = true
+      merge_method = "rebase"
+      forge = "gitea"
+
+      [deploy.web]
+      host = "box"
+      command = "make deploy"
+
+      [editor]
+      kind = "zed"
+    "#;
+    assert_eq!(problems(config), []);
+  }
+
+  #[test]
+  fn validate_suggests_close_keys() {
+    assert_eq!(
+      problems("[[checks]]\nname = \"a\"\ncommand = \"b\"\npahts = []"),
+      [problem("checks[0].pahts", "is not a known key (did you mean `paths`?)")],
+    );
+    assert_eq!(
+      problems("[shipp]"),
+      [problem("shipp", "is not a known key (did you mean `ship`?)")]
+    );
+    assert_eq!(problems("zzz = 1"), [problem("zzz", "is not a known key")]);
+  }
+
+  #[test]
+  fn validate_reports_types_enums_and_required_keys() {
+    assert_eq!(
+      problems("[ship]\ndraft = \"yes\"\nmerge_method = \"octopus\""),
+      [
+        problem("ship.draft", "should be a boolean, found string"),
+        problem("ship.merge_method", "must be one of squash, rebase, merge, found \"octopus\""),
+      ],
+    );
+    assert_eq!(
+      problems("[[checks]]\nname = \"slow\"\ntimeout = -1"),
+      [
+        problem("checks[0]", "is missing `command`"),
+        problem("checks[0].timeout", "should be an integer of at least 0, found integer"),
+      ],
+    );
+  }
+
+  #[test]
+  fn validate_checks_every_deploy_target() {
+    assert_eq!(
+      problems("[deploy.web]\nhost = \"box\"\ncomand = \"x\""),
+      [problem("deploy.web.comand", "is not a known key (did you mean `command`?)")],
+    );
+  }
+
+  #[test]
+  fn suggest_ignores_distant_names() {
+    assert_eq!(suggest("brnches", vec!["branches", "paths"]), Some("branches"));
+    assert_eq!(suggest("colour", vec!["branches", "paths"]), None);
+  }
+}
@@ -0,0 +1,79 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The `[editor]` table: what `mrt fix` and `mrt temp-strat` open their directory in. A personal
+/// choice, so it belongs in the global config or `.mrt.local.toml`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct EditorConfig {
+  #[serde(default)]
+  pub kind: EditorKind,
+  /// Program to run instead of the kind's usual launcher, e.g. `rustrover` for `jetbrains` or
+  /// `kitty` for `terminal`
+  #[serde(default)]
+  pub command: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EditorKind {
+  #[default]
+  Vscode,
+  Cursor,
+  Zed,
+  Jetbrains,
+  /// `$EDITOR` in a new terminal window
+  Terminal,
+  None,
+}
+
+impl EditorConfig {
+  /// Open the editor on `dir`, and on `file` within it if given. Returns a pattern for
+  /// `window::snap_window_left` matching the title of the window it opens, or `None` for the
+  /// `none` editor.
+  pub fn open(&self, dir: &Path, file: Option<&Path>) -> Result<Option<String>> {
+    let folder = dir.file_name().context("editor directory has no name")?.to_string_lossy();
+
+    // Every editor's window title names the folder; these are the parts around it.
+    let name = escape(&folder);
+    let (launcher, title) = match self.kind {
+      EditorKind::Vscode => ("code", format!("{name}.* Visual Studio Code$")),
+      EditorKind::Cursor => ("cursor", format!("{name}.* Cursor$")),
+      EditorKind::Zed => ("zed", format!("^{name}( |$)")),
+      EditorKind::Jetbrains => ("idea", format!("^{name}( |$)")),
+      EditorKind::Terminal => ("x-terminal-emulator", format!("^{name}$")),
+      EditorKind::None => return Ok(None),
+    };
+    let launcher = self.command.as_deref().unwrap_or(launcher);
+
+    let mut command = Command::new(launcher);
+    match self.kind {
+      // Debian's terminal emulator contract: `-T` sets the title and `-e` takes the rest of the
+      // arguments as the command. `$EDITOR` may carry flags, so let the shell split it.
+      EditorKind::Terminal => command
+        .current_dir(dir)
+        .args(["-T", &folder, "-e", "sh", "-c", "exec ${EDITOR:-vi} \"$@\"", "editor"])
+        .arg(file.unwrap_or(Path::new("."))),
+      _ => command.arg(dir).args(file),
+    };
+
+    command.spawn().with_context(|| format!("failed to run {launcher}"))?;
+    Ok(Some(title))
+  }
+}
+
+/// Escape the characters that mean something in the extended regexes `xdotool search` takes.
+fn escape(text: &str) -> String {
+  let mut escaped = String::new();
+  for c in text.chars() {
+    if ".^$*+?()[]{}|\\".contains(c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Result;
+
+use crate::config::MrtConfig;
+use crate::git;
+use crate::workspace;
+
+/// A worktree made by `mrt fix`: a `fix/` branch checked out under `<repo>/.worktrees/`.
+pub struct FixWorktree {
+  pub repo: String,
+  pub repo_dir: PathBuf,
+  pub path: PathBuf,
+  pub branch: String,
+}
+
+impl FixWorktree {
+  /// Time since the worktree was created, going by its `.git` link file.
+  pub fn age(&self) -> Option<Duration> {
+    let created = fs::metadata(self.path.join(".git")).and_then(|m| m.modified()).ok()?;
+    SystemTime::now().duration_since(created).ok()
+  }
+
+  pub fn is_dirty(&self) -> Result<bool> {
+    let status = git::stdout_in(&self.path, &["status", "--porcelain"])?;
+    Ok(!status.trim().is_empty())
+  }
+
+  /// The remote ref the fix will merge into.
+  pub fn base(&self) -> Result<String> {
+    Ok(format!("origin/{}", git::resolve_base_in(&self.path)?))
+  }
+
+  /// Commits on the branch that the base doesn't have, and the other way round.
+  pub fn ahead_behind(&self) -> Result<(u32, u32)> {
+    let range = format!("HEAD...{}", self.base()?);
+    let counts = git::stdout_in(&self.path, &["rev-list", "--left-right", "--count", &range])?;
+
+    let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+    Ok((counts.next().unwrap_or(0), counts.next().unwrap_or(0)))
+  }
+
+  /// Whether the branch has landed in the base by a fast-forward or merge commit: its tip is in
+  /// the base, and it has had commits of its own since it was created.
+  pub fn is_merged(&self) -> Result<bool> {
+    let base = self.base()?;
+    let in_base = git::stdout_in(&self.path, &["merge-base", "--is-ancestor", "HEAD", &base]);
+    if in_base.is_err() {
+      return Ok(false);
+    }
+
+    let reflog = format!("refs/heads/{}", self.branch);
+    let history = git::stdout_in(&self.path, &["reflog", "show", "--format=%H", &reflog])?;
+    Ok(history.lines().count() > 1)
+  }
+}
+
+/// Bytes used by the files under `dir`, not following symlinks.
+pub fn disk_usage(dir: &Path) -> u64 {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return 0;
+  };
+
+  entries
+    .flatten()
+    .map(|entry| match entry.file_type() {
+      Ok(kind) if kind.is_dir() => disk_usage(&entry.path()),
+      Ok(kind) if kind.is_file() => entry.metadata().map_or(0, |m| m.len()),
+      _ => 0,
+    })
+    .sum()
+}
+
+/// Every fix worktree of every repo under the projects roots, ordered by repo then branch.
+pub fn all(config: &MrtConfig) -> Result<Vec<FixWorktree>> {
+  let mut worktrees = Vec::new();
+
+  for root in workspace::roots(config)? {
+    let Ok(entries) = fs::read_dir(&root) else {
+      continue;
+    };
+
+    for entry in entries.flatten() {
+      let repo_dir = entry.path();
+      if repo_dir.join(".git").exists() {
+        worktrees.extend(in_repo(&repo_dir)?);
+      }
+    }
+  }
+
+  worktrees.sort_by(|a, b| (&a.repo, &a.branch).cmp(&(&b.repo, &b.branch)));
+  Ok(worktrees)
+}
+
+/// The fix worktrees of one repo, from `git worktree list --porcelain`.
+pub fn in_repo(repo_dir: &Path) -> Result<Vec<FixWorktree>> {
+  let repo = repo_dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+  let listing = git::stdout_in(repo_dir, &["worktree", "list", "--porcelain"])?;
+  let fix_dir = repo_dir.join(".worktrees");
+
+  let worktrees = listing
+    .split("\n\n")
+    .filter_map(|block| {
+      let path = block.lines().find_map(|l| l.strip_prefix("worktree "))?;
+      let branch = block.lines().find_map(|l| l.strip_prefix("branch refs/heads/"))?;
+      Some((PathBuf::from(path), branch.to_string()))
+    })
+    .filter(|(path, branch)| {
+      branch.starts_with("fix/") && path.starts_with(&fix_dir) && path.exists()
+    })
+    .map(|(path, branch)| FixWorktree {
+      repo: repo.clone(),
+      repo_dir: repo_dir.to_path_buf(),
+      path,
+      branch,
+    })
+    .collect();
+
+  Ok(worktrees)
+}
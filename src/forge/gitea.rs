@@ -0,0 +1,297 @@
+use std::io::Write;
+use std::process::Command;
+use std::process::Stdio;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_json::json;
+
+use super::BranchPr;
+use super::CheckRun;
+use super::CheckState;
+use super::Forge;
+use super::Issue;
+use super::MergeMethod;
+use super::NewPr;
+use super::PrHead;
+use super::PrState;
+use super::PrStatus;
+use super::Remote;
+use super::pr_number;
+
+/// Gitea, through its REST API. Authenticates with `GITEA_TOKEN`; the API lives at
+/// `https://<origin host>` unless `GITEA_URL` says otherwise.
+pub struct Gitea {
+  api: String,
+}
+
+#[derive(Deserialize)]
+struct Pull {
+  number: u64,
+  html_url: String,
+  state: String,
+  #[serde(default)]
+  merged: bool,
+  #[serde(default)]
+  mergeable: bool,
+  head: Head,
+}
+
+impl Pull {
+  fn status(&self) -> PrStatus {
+    match (self.merged, self.state.as_str()) {
+      (true, _) => PrStatus::Merged,
+      (false, "closed") => PrStatus::Closed,
+      _ => PrStatus::Open,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+struct Head {
+  #[serde(rename = "ref")]
+  branch: String,
+  sha: String,
+}
+
+#[derive(Deserialize)]
+struct GiteaIssue {
+  title: String,
+  html_url: String,
+}
+
+#[derive(Deserialize)]
+struct Label {
+  id: u64,
+  name: String,
+}
+
+#[derive(Deserialize)]
+struct CombinedStatus {
+  #[serde(default)]
+  statuses: Vec<Status>,
+}
+
+#[derive(Deserialize)]
+struct Status {
+  context: String,
+  status: String,
+  #[serde(default)]
+  target_url: String,
+}
+
+impl Gitea {
+  pub fn new(remote: Remote) -> Self {
+    Gitea { api: format!("{}/api/v1/repos/{}", base_url(&remote), remote.path) }
+  }
+
+  fn request<T: DeserializeOwned>(
+    &self, method: &str, path: &str, body: Option<Value>,
+  ) -> Result<T> {
+    let token = std::env::var("GITEA_TOKEN").context("GITEA_TOKEN not set")?;
+
+    // The token and body go to curl as a config file on stdin, keeping them off the command
+    // line where any user could read them.
+    let mut config =
+      format!("header = {}\n", curl_string(&format!("Authorization: token {token}")));
+    if let Some(body) = body {
+      config.push_str("header = \"Content-Type: application/json\"\n");
+      config.push_str(&format!("data-binary = {}\n", curl_string(&body.to_string())));
+    }
+
+    let url = format!("{}{path}", self.api);
+    let mut child = Command::new("curl")
+      .args(["-sS", "--fail-with-body", "-X", method, &url, "--config", "-"])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .context("failed to run curl")?;
+    if let Some(mut stdin) = child.stdin.take() {
+      stdin.write_all(config.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      anyhow::bail!("gitea {method} {path} failed: {stderr}{stdout}");
+    }
+
+    // Endpoints like merge reply with an empty body.
+    let stdout = match stdout.trim() {
+      "" => "null",
+      text => text,
+    };
+    serde_json::from_str(stdout).with_context(|| format!("failed to parse gitea {path} response"))
+  }
+
+  fn pull(&self, pr_url: &str) -> Result<Pull> {
+    self.request("GET", &format!("/pulls/{}", pr_number(pr_url)?), None)
+  }
+
+  /// Gitea takes label IDs, so resolve the configured names against the repo's labels.
+  fn label_ids(&self, names: &[String]) -> Result<Vec<u64>> {
+    if names.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let labels: Vec<Label> = self.request("GET", "/labels?limit=100", None)?;
+    names
+      .iter()
+      .map(|name| {
+        labels
+          .iter()
+          .find(|l| l.name == *name)
+          .map(|l| l.id)
+          .with_context(|| format!("no label named {name}"))
+      })
+      .collect()
+  }
+}
+
+impl Forge for Gitea {
+  fn name(&self) -> &'static str {
+    "gitea"
+  }
+
+  fn create_pr(&self, pr: &NewPr) -> Result<String> {
+    let body = pull_body(pr, json!(self.label_ids(pr.labels)?));
+    let created: Pull = self.request("POST", "/pulls", Some(body))?;
+
+    if !pr.reviewers.is_empty() {
+      let path = format!("/pulls/{}/requested_reviewers", created.number);
+      let _: Value = self.request("POST", &path, Some(json!({ "reviewers": pr.reviewers })))?;
+    }
+
+    Ok(created.html_url)
+  }
+
+  fn find_pr(&self, branch: &str) -> Result<Option<String>> {
+    let pulls: Vec<Pull> = self.request("GET", "/pulls?state=open&limit=50", None)?;
+    Ok(pulls.into_iter().find(|p| p.head.branch == branch).map(|p| p.html_url))
+  }
+
+  fn enable_auto_merge(&self, pr_url: &str, method: MergeMethod) -> Result<()> {
+    let path = format!("/pulls/{}/merge", pr_number(pr_url)?);
+    let _: Value = self.request("POST", &path, Some(merge_body(method)))?;
+    Ok(())
+  }
+
+  /// Labels are shown by name; the real call sends the IDs looked up from `GET /labels`.
+  fn describe_create_pr(&self, pr: &NewPr) -> Vec<String> {
+    let mut calls = Vec::new();
+    if !pr.labels.is_empty() {
+      calls.push(format!("GET {}/labels?limit=100", self.api));
+    }
+    calls.push(format!("POST {}/pulls {}", self.api, pull_body(pr, json!(pr.labels))));
+    if !pr.reviewers.is_empty() {
+      let body = json!({ "reviewers": pr.reviewers });
+      calls.push(format!("POST {}/pulls/<number>/requested_reviewers {body}", self.api));
+    }
+    calls
+  }
+
+  fn describe_auto_merge(&self, method: MergeMethod) -> String {
+    format!("POST {}/pulls/<number>/merge {}", self.api, merge_body(method))
+  }
+
+  /// Reading branch protection needs admin rights, so every status counts as required.
+  fn checks(&self, pr_url: &str, _required_only: bool) -> Result<Vec<CheckRun>> {
+    let sha = self.pull(pr_url)?.head.sha;
+    let combined: CombinedStatus = self.request("GET", &format!("/commits/{sha}/status"), None)?;
+
+    Ok(
+      combined
+        .statuses
+        .into_iter()
+        .map(|s| CheckRun {
+          state: match s.status.as_str() {
+            "success" | "warning" => CheckState::Pass,
+            "failure" | "error" => CheckState::Fail,
+            _ => CheckState::Pending,
+          },
+          name: s.context,
+          link: s.target_url,
+        })
+        .collect(),
+    )
+  }
+
+  fn pr_state(&self, pr_url: &str) -> Result<PrState> {
+    let pull = self.pull(pr_url)?;
+    let detail = match pull.mergeable {
+      true => "mergeable",
+      false => "not mergeable",
+    };
+    Ok(PrState { status: pull.status(), detail: detail.to_string() })
+  }
+
+  /// Gitea can't filter pulls by head branch, so this only sees the 50 most recent.
+  fn latest_pr(&self, branch: &str) -> Result<Option<BranchPr>> {
+    let pulls: Vec<Pull> =
+      self.request("GET", "/pulls?state=all&sort=recentupdate&limit=50", None)?;
+    let pull = pulls.into_iter().find(|p| p.head.branch == branch);
+    Ok(pull.map(|p| BranchPr { status: p.status(), url: p.html_url }))
+  }
+
+  fn failed_log(&self, _run: &CheckRun) -> Option<String> {
+    None
+  }
+
+  fn issue(&self, number: u64) -> Result<Issue> {
+    let issue: GiteaIssue = self.request("GET", &format!("/issues/{number}"), None)?;
+    Ok(Issue { title: issue.title, url: issue.html_url })
+  }
+
+  fn pr_head(&self, number: u64) -> Result<PrHead> {
+    let pull: Pull = self.request("GET", &format!("/pulls/{number}"), None)?;
+    Ok(PrHead { branch: pull.head.branch, fetch_ref: format!("refs/pull/{number}/head") })
+  }
+}
+
+/// The `POST /pulls` body. Gitea marks drafts by a `WIP:` title prefix.
+fn pull_body(pr: &NewPr, labels: Value) -> Value {
+  let title = match pr.draft {
+    true => format!("WIP: {}", pr.title),
+    false => pr.title.to_string(),
+  };
+
+  json!({
+    "title": title,
+    "body": pr.body,
+    "base": pr.base,
+    "head": pr.head,
+    "assignees": pr.assignees,
+    "labels": labels,
+  })
+}
+
+fn merge_body(method: MergeMethod) -> Value {
+  json!({ "Do": method.label(), "merge_when_checks_succeed": true })
+}
+
+/// Whether the remote's host serves the Gitea API.
+pub fn responds(remote: &Remote) -> bool {
+  let url = format!("{}/api/v1/version", base_url(remote));
+  Command::new("curl")
+    .args(["-sSf", "--max-time", "3", "-o", "/dev/null", &url])
+    .stderr(Stdio::null())
+    .status()
+    .is_ok_and(|s| s.success())
+}
+
+/// A double-quoted value for a curl config file.
+fn curl_string(value: &str) -> String {
+  format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn base_url(remote: &Remote) -> String {
+  match std::env::var("GITEA_URL") {
+    Ok(url) => url.trim_end_matches('/').to_string(),
+    Err(_) => format!("https://{}", remote.host),
+  }
+}
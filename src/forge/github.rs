@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::BranchPr;
+use super::CheckRun;
+use super::CheckState;
+use super::Forge;
+use super::Issue;
+use super::LOG_TAIL_LINES;
+use super::MergeMethod;
+use super::NewPr;
+use super::PrHead;
+use super::PrState;
+use super::PrStatus;
+use super::cli_stdout;
+use super::command_line;
+use super::tail;
+
+/// GitHub, through the `gh` CLI run in the repo at `dir`.
+pub struct GitHub {
+  pub dir: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct GhCheck {
+  name: String,
+  /// One of pass, fail, pending, skipping, cancel
+  bucket: String,
+  #[serde(default)]
+  link: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhPr {
+  url: String,
+  state: String,
+  #[serde(default)]
+  merge_state_status: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GhPrHead {
+  head_ref_name: String,
+}
+
+impl Forge for GitHub {
+  fn name(&self) -> &'static str {
+    "github"
+  }
+
+  fn create_pr(&self, pr: &NewPr) -> Result<String> {
+    Ok(cli_stdout(&self.dir, "gh", &create_args(pr))?.trim().to_string())
+  }
+
+  fn find_pr(&self, branch: &str) -> Result<Option<String>> {
+    let output = Command::new("gh")
+      .args(["pr", "view", branch, "--json", "url,state,mergeStateStatus"])
+      .current_dir(&self.dir)
+      .output()
+      .context("failed to run gh pr view")?;
+
+    // gh exits non-zero when the branch has no PR at all.
+    if !output.status.success() {
+      return Ok(None);
+    }
+
+    let pr: GhPr = serde_json::from_slice(&output.stdout).context("failed to parse gh pr view")?;
+    Ok((pr.state == "OPEN").then_some(pr.url))
+  }
+
+  fn enable_auto_merge(&self, pr_url: &str, method: MergeMethod) -> Result<()> {
+    let flag = format!("--{}", method.label());
+    cli_stdout(&self.dir, "gh", &["pr", "merge", pr_url, "--auto", &flag])?;
+    Ok(())
+  }
+
+  fn describe_create_pr(&self, pr: &NewPr) -> Vec<String> {
+    vec![command_line("gh", &create_args(pr))]
+  }
+
+  fn describe_auto_merge(&self, method: MergeMethod) -> String {
+    let flag = format!("--{}", method.label());
+    command_line("gh", &["pr", "merge", "<pr-url>", "--auto", &flag])
+  }
+
+  fn checks(&self, pr_url: &str, required_only: bool) -> Result<Vec<CheckRun>> {
+    let mut command = Command::new("gh");
+    command.args(["pr", "checks", pr_url, "--json", "name,bucket,link"]).current_dir(&self.dir);
+    if required_only {
+      command.arg("--required");
+    }
+
+    // gh exits non-zero while checks are pending, so go by whether it printed anything.
+    let output = command.output().context("failed to run gh pr checks")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+      return Ok(Vec::new());
+    }
+
+    let checks: Vec<GhCheck> =
+      serde_json::from_str(&stdout).context("failed to parse gh pr checks output")?;
+
+    Ok(
+      checks
+        .into_iter()
+        .map(|c| CheckRun {
+          state: match c.bucket.as_str() {
+            "pass" => CheckState::Pass,
+            "fail" | "cancel" => CheckState::Fail,
+            "skipping" => CheckState::Skipped,
+            _ => CheckState::Pending,
+          },
+          name: c.name,
+          link: c.link,
+        })
+        .collect(),
+    )
+  }
+
+  fn pr_state(&self, pr_url: &str) -> Result<PrState> {
+    let stdout = cli_stdout(
+      &self.dir,
+      "gh",
+      &["pr", "view", pr_url, "--json", "url,state,mergeStateStatus"],
+    )?;
+    let pr: GhPr = serde_json::from_str(&stdout).context("failed to parse gh pr view output")?;
+
+    Ok(PrState { status: status(&pr.state), detail: pr.merge_state_status.to_lowercase() })
+  }
+
+  fn latest_pr(&self, branch: &str) -> Result<Option<BranchPr>> {
+    let stdout = cli_stdout(
+      &self.dir,
+      "gh",
+      &["pr", "list", "--head", branch, "--state", "all", "--limit", "1", "--json", "url,state"],
+    )?;
+    let prs: Vec<GhPr> = serde_json::from_str(&stdout).context("failed to parse gh pr list")?;
+    Ok(prs.into_iter().next().map(|pr| BranchPr { status: status(&pr.state), url: pr.url }))
+  }
+
+  /// Only GitHub Actions jobs have logs `gh` can fetch; other check providers just get a link.
+  fn failed_log(&self, run: &CheckRun) -> Option<String> {
+    let (_, job_id) = run.link.split_once("/job/")?;
+    let job_id = job_id.split(['/', '?', '#']).next()?;
+
+    let log =
+      cli_stdout(&self.dir, "gh", &["run", "view", "--job", job_id, "--log-failed"]).ok()?;
+    Some(tail(&log, LOG_TAIL_LINES))
+  }
+
+  fn issue(&self, number: u64) -> Result<Issue> {
+    let stdout =
+      cli_stdout(&self.dir, "gh", &["issue", "view", &number.to_string(), "--json", "title,url"])?;
+    serde_json::from_str(&stdout).context("failed to parse gh issue view output")
+  }
+
+  fn pr_head(&self, number: u64) -> Result<PrHead> {
+    let stdout =
+      cli_stdout(&self.dir, "gh", &["pr", "view", &number.to_string(), "--json", "headRefName"])?;
+    let head: GhPrHead =
+      serde_json::from_str(&stdout).context("failed to parse gh pr view output")?;
+    Ok(PrHead { branch: head.head_ref_name, fetch_ref: format!("refs/pull/{number}/head") })
+  }
+}
+
+fn create_args<'a>(pr: &'a NewPr) -> Vec<&'a str> {
+  let mut args = vec![
+    "pr", "create", "--title", pr.title, "--body", pr.body, "--base", pr.base, "--head", pr.head,
+  ];
+  if pr.draft {
+    args.push("--draft");
+  }
+  for (flag, values) in
+    [("--reviewer", pr.reviewers), ("--label", pr.labels), ("--assignee", pr.assignees)]
+  {
+    for value in values {
+      args.extend([flag, value.as_str()]);
+    }
+  }
+  args
+}
+
+fn status(state: &str) -> PrStatus {
+  match state {
+    "MERGED" => PrStatus::Merged,
+    "CLOSED" => PrStatus::Closed,
+    _ => PrStatus::Open,
+  }
+}
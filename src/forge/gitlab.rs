@@ -0,0 +1,222 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::BranchPr;
+use super::CheckRun;
+use super::CheckState;
+use super::Forge;
+use super::Issue;
+use super::LOG_TAIL_LINES;
+use super::MergeMethod;
+use super::NewPr;
+use super::PrHead;
+use super::PrState;
+use super::PrStatus;
+use super::cli_stdout;
+use super::command_line;
+use super::pr_number;
+use super::tail;
+
+/// GitLab, through the `glab` CLI run in the repo at `dir`.
+pub struct GitLab {
+  pub dir: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct MergeRequest {
+  web_url: String,
+  state: String,
+  #[serde(default)]
+  detailed_merge_status: String,
+  #[serde(default)]
+  source_branch: String,
+  #[serde(default)]
+  head_pipeline: Option<Pipeline>,
+}
+
+#[derive(Deserialize)]
+struct GlIssue {
+  title: String,
+  web_url: String,
+}
+
+#[derive(Deserialize)]
+struct Pipeline {
+  id: u64,
+}
+
+#[derive(Deserialize)]
+struct Job {
+  id: u64,
+  name: String,
+  status: String,
+  #[serde(default)]
+  allow_failure: bool,
+  #[serde(default)]
+  web_url: String,
+}
+
+impl GitLab {
+  fn merge_request(&self, pr_url: &str) -> Result<MergeRequest> {
+    let iid = pr_number(pr_url)?;
+    let json =
+      cli_stdout(&self.dir, "glab", &["api", &format!("projects/:id/merge_requests/{iid}")])?;
+    serde_json::from_str(&json).context("failed to parse merge request")
+  }
+
+  fn jobs(&self, pr_url: &str) -> Result<Vec<Job>> {
+    let Some(pipeline) = self.merge_request(pr_url)?.head_pipeline else {
+      return Ok(Vec::new());
+    };
+
+    let path = format!("projects/:id/pipelines/{}/jobs", pipeline.id);
+    let json = cli_stdout(&self.dir, "glab", &["api", &path])?;
+    serde_json::from_str(&json).context("failed to parse pipeline jobs")
+  }
+}
+
+impl Forge for GitLab {
+  fn name(&self) -> &'static str {
+    "gitlab"
+  }
+
+  fn create_pr(&self, pr: &NewPr) -> Result<String> {
+    let args = create_args(pr);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    // glab prints progress around the URL, so pick out the line that is one.
+    let stdout = cli_stdout(&self.dir, "glab", &args)?;
+    stdout
+      .lines()
+      .map(str::trim)
+      .find(|line| line.starts_with("http"))
+      .map(str::to_string)
+      .context("glab mr create printed no merge request URL")
+  }
+
+  fn find_pr(&self, branch: &str) -> Result<Option<String>> {
+    let path = format!("projects/:id/merge_requests?state=opened&source_branch={branch}");
+    let json = cli_stdout(&self.dir, "glab", &["api", &path])?;
+    let mrs: Vec<MergeRequest> =
+      serde_json::from_str(&json).context("failed to parse merge requests")?;
+    Ok(mrs.into_iter().find(|mr| mr.state == "opened").map(|mr| mr.web_url))
+  }
+
+  fn enable_auto_merge(&self, pr_url: &str, method: MergeMethod) -> Result<()> {
+    cli_stdout(&self.dir, "glab", &merge_args(pr_number(pr_url)?, method))?;
+    Ok(())
+  }
+
+  fn describe_create_pr(&self, pr: &NewPr) -> Vec<String> {
+    let args = create_args(pr);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    vec![command_line("glab", &args)]
+  }
+
+  fn describe_auto_merge(&self, method: MergeMethod) -> String {
+    command_line("glab", &merge_args("<iid>", method))
+  }
+
+  /// Jobs with `allow_failure` don't block the pipeline, so they aren't required.
+  fn checks(&self, pr_url: &str, required_only: bool) -> Result<Vec<CheckRun>> {
+    let jobs = self.jobs(pr_url)?;
+
+    Ok(
+      jobs
+        .into_iter()
+        .filter(|job| !required_only || !job.allow_failure)
+        .map(|job| CheckRun {
+          state: match job.status.as_str() {
+            "success" => CheckState::Pass,
+            "failed" | "canceled" => CheckState::Fail,
+            "skipped" | "manual" => CheckState::Skipped,
+            _ => CheckState::Pending,
+          },
+          name: job.name,
+          link: match job.web_url.is_empty() {
+            true => format!("job {}", job.id),
+            false => job.web_url,
+          },
+        })
+        .collect(),
+    )
+  }
+
+  fn pr_state(&self, pr_url: &str) -> Result<PrState> {
+    let mr = self.merge_request(pr_url)?;
+    Ok(PrState { status: status(&mr.state), detail: mr.detailed_merge_status })
+  }
+
+  /// The API lists merge requests newest first.
+  fn latest_pr(&self, branch: &str) -> Result<Option<BranchPr>> {
+    let path = format!("projects/:id/merge_requests?source_branch={branch}&per_page=1");
+    let json = cli_stdout(&self.dir, "glab", &["api", &path])?;
+    let mrs: Vec<MergeRequest> =
+      serde_json::from_str(&json).context("failed to parse merge requests")?;
+    Ok(mrs.into_iter().next().map(|mr| BranchPr { status: status(&mr.state), url: mr.web_url }))
+  }
+
+  fn failed_log(&self, run: &CheckRun) -> Option<String> {
+    let (_, job_id) = run.link.rsplit_once("/jobs/")?;
+    let trace =
+      cli_stdout(&self.dir, "glab", &["api", &format!("projects/:id/jobs/{job_id}/trace")])
+        .ok()?;
+    Some(tail(&trace, LOG_TAIL_LINES))
+  }
+
+  fn issue(&self, number: u64) -> Result<Issue> {
+    let json = cli_stdout(&self.dir, "glab", &["api", &format!("projects/:id/issues/{number}")])?;
+    let issue: GlIssue = serde_json::from_str(&json).context("failed to parse issue")?;
+    Ok(Issue { title: issue.title, url: issue.web_url })
+  }
+
+  fn pr_head(&self, number: u64) -> Result<PrHead> {
+    let json =
+      cli_stdout(&self.dir, "glab", &["api", &format!("projects/:id/merge_requests/{number}")])?;
+    let mr: MergeRequest = serde_json::from_str(&json).context("failed to parse merge request")?;
+    let fetch_ref = format!("refs/merge-requests/{number}/head");
+    Ok(PrHead { branch: mr.source_branch, fetch_ref })
+  }
+}
+
+fn status(state: &str) -> PrStatus {
+  match state {
+    "merged" => PrStatus::Merged,
+    "closed" => PrStatus::Closed,
+    _ => PrStatus::Open,
+  }
+}
+
+/// glab takes each list flag once, comma-separated.
+fn create_args(pr: &NewPr) -> Vec<String> {
+  let mut args: Vec<String> = [
+    "mr", "create", "--yes", "--title", pr.title, "--description", pr.body, "--target-branch",
+    pr.base, "--source-branch", pr.head,
+  ]
+  .map(str::to_string)
+  .to_vec();
+  if pr.draft {
+    args.push("--draft".to_string());
+  }
+  for (flag, values) in
+    [("--reviewer", pr.reviewers), ("--label", pr.labels), ("--assignee", pr.assignees)]
+  {
+    if !values.is_empty() {
+      args.extend([flag.to_string(), values.join(",")]);
+    }
+  }
+  args
+}
+
+fn merge_args(iid: &str, method: MergeMethod) -> Vec<&str> {
+  let mut args = vec!["mr", "merge", iid, "--yes", "--auto-merge"];
+  match method {
+    MergeMethod::Squash => args.push("--squash"),
+    MergeMethod::Rebase => args.push("--rebase"),
+    MergeMethod::Merge => {},
+  }
+  args
+}
@@ -0,0 +1,324 @@
+mod gitea;
+mod github;
+mod gitlab;
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+
+/// Prefix of detection notes, pointing at the setting that makes detection unnecessary.
+const FORGE_HINT: &str = "no [ship] forge configured";
+
+/// How much of a failed CI job's log to show.
+const LOG_TAIL_LINES: usize = 40;
+
+/// The code forge hosting `origin`, which ship talks to for PRs and CI state.
+pub trait Forge {
+  fn name(&self) -> &'static str;
+
+  /// Open a PR and return its URL.
+  fn create_pr(&self, pr: &NewPr) -> Result<String>;
+
+  /// URL of the open PR whose head is `branch`, if there is one.
+  fn find_pr(&self, branch: &str) -> Result<Option<String>>;
+
+  fn enable_auto_merge(&self, pr_url: &str, method: MergeMethod) -> Result<()>;
+
+  /// The commands or API calls [`Forge::create_pr`] would make, for `mrt ship --dry-run`.
+  fn describe_create_pr(&self, pr: &NewPr) -> Vec<String>;
+
+  /// The command or API call [`Forge::enable_auto_merge`] would make on the not yet created PR.
+  fn describe_auto_merge(&self, method: MergeMethod) -> String;
+
+  /// CI checks on the PR's head commit. With `required_only`, just those that gate merging.
+  fn checks(&self, pr_url: &str, required_only: bool) -> Result<Vec<CheckRun>>;
+
+  fn pr_state(&self, pr_url: &str) -> Result<PrState>;
+
+  /// The most recent PR whose head is `branch`, whatever its state.
+  fn latest_pr(&self, branch: &str) -> Result<Option<BranchPr>>;
+
+  /// The tail of a failed check's log, where the forge can provide one.
+  fn failed_log(&self, run: &CheckRun) -> Option<String>;
+
+  fn issue(&self, number: u64) -> Result<Issue>;
+
+  fn pr_head(&self, number: u64) -> Result<PrHead>;
+}
+
+#[derive(Deserialize)]
+pub struct Issue {
+  pub title: String,
+  pub url: String,
+}
+
+/// Where a PR's commits live: its head branch, and a ref on origin that has them even when the
+/// branch is on a fork.
+pub struct PrHead {
+  pub branch: String,
+  pub fetch_ref: String,
+}
+
+pub struct NewPr<'a> {
+  pub title: &'a str,
+  pub body: &'a str,
+  pub base: &'a str,
+  pub head: &'a str,
+  pub draft: bool,
+  pub reviewers: &'a [String],
+  pub labels: &'a [String],
+  pub assignees: &'a [String],
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+  #[default]
+  Squash,
+  Rebase,
+  Merge,
+}
+
+impl MergeMethod {
+  pub fn label(self) -> &'static str {
+    match self {
+      MergeMethod::Squash => "squash",
+      MergeMethod::Rebase => "rebase",
+      MergeMethod::Merge => "merge",
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CheckState {
+  Pass,
+  Fail,
+  Pending,
+  Skipped,
+}
+
+impl CheckState {
+  pub fn label(self) -> &'static str {
+    match self {
+      CheckState::Pass => "pass",
+      CheckState::Fail => "fail",
+      CheckState::Pending => "pending",
+      CheckState::Skipped => "skipped",
+    }
+  }
+}
+
+pub struct CheckRun {
+  pub name: String,
+  pub state: CheckState,
+  pub link: String,
+}
+
+#[derive(PartialEq)]
+pub enum PrStatus {
+  Open,
+  Merged,
+  Closed,
+}
+
+impl PrStatus {
+  pub fn label(&self) -> &'static str {
+    match self {
+      PrStatus::Open => "open",
+      PrStatus::Merged => "merged",
+      PrStatus::Closed => "closed",
+    }
+  }
+}
+
+pub struct BranchPr {
+  pub url: String,
+  pub status: PrStatus,
+}
+
+pub struct PrState {
+  pub status: PrStatus,
+  /// Forge-specific merge readiness, e.g. GitHub's `BLOCKED` or GitLab's `ci_must_pass`
+  pub detail: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+  GitHub,
+  GitLab,
+  Gitea,
+}
+
+/// Pick the forge for `origin`, from `kind` if configured or else from the remote's host.
+pub fn detect(kind: Option<ForgeKind>) -> Result<Box<dyn Forge>> {
+  detect_in(Path::new("."), kind)
+}
+
+/// Like [`detect`], for the repo at `dir`.
+pub fn detect_in(dir: &Path, kind: Option<ForgeKind>) -> Result<Box<dyn Forge>> {
+  let remote = Remote::origin_in(dir);
+
+  let kind = match (kind, &remote) {
+    (Some(kind), _) => kind,
+    (None, Ok(r)) if r.host.contains("github") => ForgeKind::GitHub,
+    (None, Ok(r)) if r.host.contains("gitlab") => ForgeKind::GitLab,
+    (None, Ok(r)) if r.host.contains("gitea") || r.host == "codeberg.org" => ForgeKind::Gitea,
+    // Self-hosted Gitea instances rarely say so in their hostname, so ask the API.
+    (None, Ok(r)) => {
+      eprintln!("{FORGE_HINT}: checking whether {} runs Gitea", r.host);
+      match gitea::responds(r) {
+        true => ForgeKind::Gitea,
+        false => {
+          eprintln!("{FORGE_HINT}: {} doesn't answer as Gitea, assuming GitHub", r.host);
+          ForgeKind::GitHub
+        },
+      }
+    },
+    (None, Err(_)) => ForgeKind::GitHub,
+  };
+
+  Ok(match kind {
+    ForgeKind::GitHub => Box::new(GitHub { dir: dir.to_path_buf() }),
+    ForgeKind::GitLab => Box::new(GitLab { dir: dir.to_path_buf() }),
+    ForgeKind::Gitea => Box::new(Gitea::new(remote?)),
+  })
+}
+
+/// Host and `owner/repo` path of a git remote URL.
+pub struct Remote {
+  pub host: String,
+  pub path: String,
+}
+
+impl Remote {
+  /// The `origin` remote of the repo at `dir`.
+  pub fn origin_in(dir: &Path) -> Result<Self> {
+    let output = Command::new("git")
+      .args(["remote", "get-url", "origin"])
+      .current_dir(dir)
+      .output()
+      .context("failed to run git remote get-url")?;
+
+    if !output.status.success() {
+      anyhow::bail!("no origin remote");
+    }
+
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Remote::parse(&url).with_context(|| format!("can't parse origin url: {url}"))
+  }
+
+  /// Handles `https://host/owner/repo.git`, `ssh://git@host:22/owner/repo.git` and
+  /// `git@host:owner/repo.git`.
+  fn parse(url: &str) -> Option<Self> {
+    let (host, path) = match url.split_once("://") {
+      Some((_, rest)) => rest.split_once('/')?,
+      None => url.split_once(':')?,
+    };
+
+    let host = host.rsplit('@').next()?.split(':').next()?.to_string();
+    let path = path.trim_end_matches('/').trim_end_matches(".git").to_string();
+    Some(Remote { host, path })
+  }
+}
+
+/// The last path segment of a PR URL, which every forge uses as the PR number.
+fn pr_number(pr_url: &str) -> Result<&str> {
+  pr_url
+    .trim_end_matches('/')
+    .rsplit('/')
+    .next()
+    .filter(|n| n.chars().all(|c| c.is_ascii_digit()))
+    .with_context(|| format!("no PR number in {pr_url}"))
+}
+
+/// `program` and `args` as a shell command line, quoting the arguments that need it.
+fn command_line(program: &str, args: &[&str]) -> String {
+  let mut line = program.to_string();
+  for arg in args {
+    let plain =
+      !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,@".contains(c));
+    match plain {
+      true => line.push_str(&format!(" {arg}")),
+      false => line.push_str(&format!(" '{}'", arg.replace('\'', "'\\''"))),
+    }
+  }
+  line
+}
+
+/// Run a forge CLI in `dir` and return its stdout, bailing with its stderr on failure.
+fn cli_stdout(dir: &Path, program: &str, args: &[&str]) -> Result<String> {
+  let output =
+    Command::new(program).args(args).current_dir(dir).output().with_context(|| {
+      format!("failed to run {program} {}", args[..2.min(args.len())].join(" "))
+    })?;
+
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    anyhow::bail!("{program} {} failed: {stderr}", args[..2.min(args.len())].join(" "));
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn tail(log: &str, lines: usize) -> String {
+  let all: Vec<&str> = log.lines().collect();
+  all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parsed(url: &str) -> Option<(String, String)> {
+    Remote::parse(url).map(|r| (r.host, r.path))
+  }
+
+  #[test]
+  fn remote_parse_handles_https_ssh_and_scp_urls() {
+    let expected = Some(("github.com".to_string(), "owner/repo".to_string()));
+    assert_eq!(parsed("https://github.com/owner/repo.git"), expected);
+    assert_eq!(parsed("https://github.com/owner/repo/"), expected);
+    assert_eq!(parsed("ssh://git@github.com:22/owner/repo.git"), expected);
+    assert_eq!(parsed("git@github.com:owner/repo.git"), expected);
+    assert_eq!(parsed("https://user@github.com/owner/repo"), expected);
+  }
+
+  #[test]
+  fn remote_parse_keeps_nested_group_paths() {
+    assert_eq!(
+      parsed("git@gitlab.example.com:group/sub/repo.git"),
+      Some(("gitlab.example.com".to_string(), "group/sub/repo".to_string())),
+    );
+  }
+
+  #[test]
+  fn remote_parse_rejects_local_paths() {
+    assert_eq!(parsed("/srv/git/repo.git"), None);
+  }
+
+  #[test]
+  fn pr_number_is_the_last_numeric_segment() {
+    assert_eq!(pr_number("https://github.com/o/r/pull/42").unwrap(), "42");
+    assert_eq!(pr_number("https://gitlab.com/o/r/-/merge_requests/7/").unwrap(), "7");
+    assert!(pr_number("https://github.com/o/r/pull/new").is_err());
+  }
+
+  #[test]
+  fn command_line_quotes_arguments_for_the_shell() {
+    assert_eq!(command_line("gh", &["pr", "merge", "--auto"]), "gh pr merge --auto");
+    assert_eq!(
+      command_line("gh", &["--title", "Don't panic", "--body", ""]),
+      "gh --title 'Don'\\''t panic' --body ''"
+    );
+    assert_eq!(command_line("glab", &["mr", "merge", "<iid>"]), "glab mr merge '<iid>'");
+  }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::worktree_meta::WorktreeMeta;
+
+/// Run git in the current directory and return its stdout, bailing with stderr on failure.
+pub fn stdout(args: &[&str]) -> Result<String> {
+  stdout_in(Path::new("."), args)
+}
+
+/// Like [`stdout`], running git in `dir`.
+pub fn stdout_in(dir: &Path, args: &[&str]) -> Result<String> {
+  let output = Command::new("git")
+    .args(args)
+    .current_dir(dir)
+    .output()
+    .with_context(|| format!("failed to run git {}", args[0]))?;
+
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    anyhow::bail!("git {} failed: {stderr}", args[0]);
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `<git dir>/mrt`, where mrt keeps per-worktree state such as the ship journal.
+pub fn mrt_dir() -> Result<PathBuf> {
+  mrt_dir_in(Path::new("."))
+}
+
+/// The [`mrt_dir`] of the repo or worktree at `dir`.
+pub fn mrt_dir_in(dir: &Path) -> Result<PathBuf> {
+  let git_dir = stdout_in(dir, &["rev-parse", "--absolute-git-dir"])
+    .with_context(|| format!("{} is not a git repository", dir.display()))?;
+  Ok(PathBuf::from(git_dir.trim()).join("mrt"))
+}
+
+/// Hash the working tree as `git add -A` would stage it, using a scratch copy of the index so
+/// the real one is left alone. The copy is per process, so a `mrt check --watch` hashing away in
+/// the background doesn't trip over a ship doing the same.
+pub fn working_tree_hash() -> Result<String> {
+  let dir = mrt_dir()?;
+  fs::create_dir_all(&dir)?;
+  let scratch = dir.join(format!("scratch-index-{}", std::process::id()));
+  let index = stdout(&["rev-parse", "--git-path", "index"])?;
+  let _ = fs::copy(index.trim(), &scratch);
+
+  let git = |args: &[&str]| -> Result<String> {
+    let output = Command::new("git")
+      .args(args)
+      .env("GIT_INDEX_FILE", &scratch)
+      .output()
+      .with_context(|| format!("failed to run git {}", args[0]))?;
+
+    if !output.status.success() {
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      anyhow::bail!("git {} failed: {stderr}", args[0]);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  };
+
+  git(&["add", "-A"])?;
+  let tree = git(&["write-tree"]);
+  let _ = fs::remove_file(&scratch);
+  tree
+}
+
+pub fn current_branch() -> Result<String> {
+  let output = Command::new("git")
+    .args(["rev-parse", "--abbrev-ref", "HEAD"])
+    .output()
+    .context("failed to detect current branch")?;
+
+  if !output.status.success() {
+    anyhow::bail!("failed to detect current branch");
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Paths this ship would touch relative to the base: commits on the branch, uncommitted edits
+/// to tracked files, and untracked files that `git add .` would pick up.
+pub fn changed_paths(base: &str) -> Result<Vec<String>> {
+  let merge_base = stdout(&["merge-base", &format!("origin/{base}"), "HEAD"])?;
+  let diff = stdout(&["diff", "--name-only", "--no-renames", merge_base.trim()])?;
+  let untracked = stdout(&["ls-files", "--others", "--exclude-standard"])?;
+
+  Ok(diff.lines().chain(untracked.lines()).map(str::to_string).collect())
+}
+
+/// The branch on origin that work here merges into: the base `mrt fix --from` recorded for this
+/// worktree while origin still has it, or else the default branch, `main` or `master`.
+pub fn resolve_base() -> Result<String> {
+  resolve_base_in(Path::new("."))
+}
+
+/// Like [`resolve_base`], for the repo or worktree at `dir`.
+pub fn resolve_base_in(dir: &Path) -> Result<String> {
+  let recorded = WorktreeMeta::load_in(dir)?.and_then(|meta| meta.base);
+  let candidates = recorded.as_deref().into_iter().chain(["main", "master"]);
+
+  for candidate in candidates {
+    let remote = format!("origin/{candidate}");
+    if stdout_in(dir, &["rev-parse", "--verify", "--quiet", &remote]).is_ok() {
+      return Ok(candidate.to_string());
+    }
+  }
+
+  anyhow::bail!("could not find origin/main or origin/master");
+}
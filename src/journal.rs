@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::checks::CheckOutcome;
+use crate::checks::CheckStatus;
+use crate::git;
+
+/// The steps of a ship, in the order they run.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+  Checks,
+  Add,
+  Commit,
+  Rebase,
+  Push,
+  Pr,
+  AutoMerge,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PassedCheck {
+  name: String,
+  secs: f64,
+}
+
+/// Progress of an in-flight ship, persisted under `.git/mrt/` so `mrt ship --resume` can pick
+/// up after a failed step.
+#[derive(Serialize, Deserialize)]
+pub struct Journal {
+  pub message: String,
+  #[serde(default)]
+  pub branch: Option<String>,
+  #[serde(default)]
+  pub pr_url: Option<String>,
+  /// HEAD before the rebase step started, so its incoming changes can be found afterwards
+  #[serde(default)]
+  pub rebased_from: Option<String>,
+  /// Whether the forge accepted the request to merge once checks pass
+  #[serde(default)]
+  pub auto_merge_enabled: bool,
+  #[serde(default)]
+  done: Vec<Step>,
+  #[serde(default)]
+  checks: Vec<PassedCheck>,
+  #[serde(skip)]
+  path: PathBuf,
+}
+
+impl Journal {
+  /// Start a fresh journal for a new ship.
+  pub fn start(message: &str) -> Result<Self> {
+    let path = journal_path()?;
+    Ok(Journal {
+      message: message.to_string(),
+      branch: None,
+      pr_url: None,
+      rebased_from: None,
+      auto_merge_enabled: false,
+      done: Vec::new(),
+      checks: Vec::new(),
+      path,
+    })
+  }
+
+  /// Load the journal of an unfinished ship, if there is one.
+  pub fn load() -> Result<Option<Self>> {
+    let path = journal_path()?;
+    let Ok(contents) = fs::read_to_string(&path) else {
+      return Ok(None);
+    };
+
+    let mut journal: Journal =
+      toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))?;
+    journal.path = path;
+    Ok(Some(journal))
+  }
+
+  pub fn is_done(&self, step: Step) -> bool {
+    self.done.contains(&step)
+  }
+
+  /// Mark a step finished and write the journal to disk.
+  pub fn finish(&mut self, step: Step) -> Result<()> {
+    if !self.is_done(step) {
+      self.done.push(step);
+    }
+    self.save()
+  }
+
+  pub fn save(&self) -> Result<()> {
+    if let Some(dir) = self.path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string(self).context("failed to serialize ship journal")?;
+    fs::write(&self.path, contents)
+      .with_context(|| format!("failed to write {}", self.path.display()))
+  }
+
+  /// Remove the journal once the ship has completed.
+  pub fn clear(self) -> Result<()> {
+    match fs::remove_file(&self.path) {
+      Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+      _ => Ok(()),
+    }
+  }
+
+  pub fn record_checks(&mut self, outcomes: &[CheckOutcome]) {
+    self.checks = outcomes
+      .iter()
+      .filter(|o| o.passed())
+      .map(|o| PassedCheck { name: o.name.clone(), secs: o.duration.as_secs_f64() })
+      .collect();
+  }
+
+  /// The passing checks recorded by the checks step, for the PR description.
+  pub fn check_outcomes(&self) -> Vec<CheckOutcome> {
+    self
+      .checks
+      .iter()
+      .map(|c| CheckOutcome {
+        name: c.name.clone(),
+        status: CheckStatus::Passed,
+        duration: Duration::from_secs_f64(c.secs),
+        stdout: String::new(),
+        stderr: String::new(),
+      })
+      .collect()
+  }
+}
+
+/// `.git/mrt/ship.toml`, using the per-worktree git dir so fix worktrees each get their own.
+fn journal_path() -> Result<PathBuf> {
+  Ok(git::mrt_dir()?.join("ship.toml"))
+}
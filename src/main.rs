@@ -2,12 +2,31 @@ use anyhow::Result;
 use clap::Parser;
 use clap::Subcommand;
 
+mod check_cache;
+mod check_report;
+mod check_runner;
+mod checks;
+mod ci_watch;
 mod commands;
+mod config;
+mod config_schema;
+mod editor;
+mod fix_worktree;
+mod forge;
+mod git;
+mod journal;
 mod name_generator;
+mod pr_description;
+mod secrets;
+mod staging;
 pub mod utils;
 pub mod window;
+mod workspace;
+mod worktree_meta;
 
+use commands::CheckCommand;
 use commands::ClaudeCommand;
+use commands::ConfigCommand;
 use commands::DeployCommand;
 use commands::FixCommand;
 use commands::ShipCommand;
@@ -23,8 +42,12 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-  /// Launch Claude with cwd set to ~/projects
+  /// Run the .mrt.toml checks that ship would run
+  Check(CheckCommand),
+  /// Launch Claude with cwd set to the projects root
   Claude(ClaudeCommand),
+  /// Inspect mrt's merged configuration
+  Config(ConfigCommand),
   /// Deploy updates to remote services
   Deploy(DeployCommand),
   /// Start a fix workflow for a repository
@@ -41,7 +64,9 @@ fn main() -> Result<()> {
   let cli = Cli::parse();
 
   match cli.command {
+    Commands::Check(cmd) => cmd.execute(),
     Commands::Claude(cmd) => cmd.execute(),
+    Commands::Config(cmd) => cmd.execute(),
     Commands::Deploy(cmd) => cmd.execute(),
     Commands::Fix(cmd) => cmd.execute(),
     Commands::Ship(cmd) => cmd.execute(),
@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::checks::CheckOutcome;
+use crate::git;
+
+const TEMPLATE_PATHS: &[&str] =
+  &[".github/pull_request_template.md", ".github/PULL_REQUEST_TEMPLATE.md"];
+
+/// An issue the PR mentions. Only an explicit link closes the issue on merge; one inferred from
+/// the message or branch name just refers to it.
+pub enum IssueLink {
+  Closes(String),
+  Refs(String),
+}
+
+pub struct PrDescription {
+  pub title: String,
+  pub body: String,
+}
+
+/// What ship knows about the change, rendered into the PR body.
+struct Sections {
+  summary: String,
+  changes: String,
+  checks: String,
+  issue: String,
+}
+
+/// Build the PR title and body from the commit message, diffstat, passing checks and any
+/// referenced issue. Fills the repo's pull request template when it has one.
+pub fn build(
+  message: &str, diffstat: &str, outcomes: &[CheckOutcome], issue: Option<&IssueLink>,
+) -> PrDescription {
+  let (title, summary) = message.split_once('\n').unwrap_or((message, ""));
+
+  let checks: Vec<String> = outcomes
+    .iter()
+    .filter(|o| o.passed())
+    .map(|o| format!("- {}: passed ({:.1}s)", o.name, o.duration.as_secs_f64()))
+    .collect();
+
+  let sections = Sections {
+    summary: summary.trim().to_string(),
+    changes: match diffstat.trim_end() {
+      "" => String::new(),
+      stat => format!("```\n{stat}\n```"),
+    },
+    checks: checks.join("\n"),
+    issue: match issue {
+      Some(IssueLink::Closes(issue)) => format!("Closes {issue}"),
+      Some(IssueLink::Refs(issue)) => format!("Refs {issue}"),
+      None => String::new(),
+    },
+  };
+
+  // The template lives at the repo root, whichever directory ship runs from.
+  let top = git::stdout(&["rev-parse", "--show-toplevel"]).map(|top| PathBuf::from(top.trim()));
+  let top = top.unwrap_or_default();
+  let template = TEMPLATE_PATHS.iter().find_map(|path| fs::read_to_string(top.join(path)).ok());
+  let body = match template {
+    Some(template) => fill_template(&template, &sections),
+    None => default_body(&sections),
+  };
+
+  PrDescription { title: title.trim().to_string(), body }
+}
+
+/// Find an issue reference: `#123` in the message, or a number leading the name after the
+/// branch's prefix, as in `fix/123-null-price`. Numbers elsewhere, like the 18 in
+/// `upgrade-node-18`, are usually versions or years rather than issues.
+pub fn issue_reference(message: &str, branch: &str) -> Option<String> {
+  let from_message = message.split('#').skip(1).find_map(|rest| {
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    (!digits.is_empty()).then_some(digits)
+  });
+
+  let from_branch = || {
+    let (_, name) = branch.split_once('/')?;
+    let (number, _) = name.split_once('-')?;
+    let numeric = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    numeric.then(|| number.to_string())
+  };
+
+  from_message.or_else(from_branch).map(|n| format!("#{n}"))
+}
+
+fn default_body(sections: &Sections) -> String {
+  let mut parts = Vec::new();
+
+  if !sections.summary.is_empty() {
+    parts.push(sections.summary.clone());
+  }
+  if !sections.changes.is_empty() {
+    parts.push(format!("## Changes\n\n{}", sections.changes));
+  }
+  if !sections.checks.is_empty() {
+    parts.push(format!("## Checks\n\n{}", sections.checks));
+  }
+  if !sections.issue.is_empty() {
+    parts.push(sections.issue.clone());
+  }
+
+  parts.join("\n\n")
+}
+
+/// Drop each section under the first template heading that looks like it wants it, and
+/// append whatever didn't find a home.
+fn fill_template(template: &str, sections: &Sections) -> String {
+  let mut slots: [(&[&str], &str, bool); 4] = [
+    (&["summary", "description", "what"], &sections.summary, false),
+    (&["change"], &sections.changes, false),
+    (&["test", "check", "verif"], &sections.checks, false),
+    (&["issue", "ticket", "related", "closes", "fixes"], &sections.issue, false),
+  ];
+
+  let mut out = String::new();
+  for line in template.lines() {
+    out.push_str(line);
+    out.push('\n');
+
+    if !line.trim_start().starts_with('#') {
+      continue;
+    }
+
+    let heading = line.trim_start_matches('#').trim().to_lowercase();
+    let slot = slots.iter_mut().find(|(keywords, content, used)| {
+      !used && !content.is_empty() && keywords.iter().any(|k| heading.contains(k))
+    });
+    if let Some((_, content, used)) = slot {
+      out.push_str(&format!("\n{content}\n"));
+      *used = true;
+    }
+  }
+
+  for (_, content, used) in slots {
+    if !used && !content.is_empty() {
+      out.push_str(&format!("\n{content}\n"));
+    }
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sections() -> Sections {
+    Sections {
+      summary: "Guard against empty quotes.".to_string(),
+      changes: "```\n src/quote.rs | 4 ++--\n```".to_string(),
+      checks: "- test: passed (1.0s)".to_string(),
+      issue: "Closes #7".to_string(),
+    }
+  }
+
+  #[test]
+  fn issue_reference_prefers_the_message() {
+    assert_eq!(issue_reference("Fix crash (#42)", "fix/7-crash").as_deref(), Some("#42"));
+    assert_eq!(issue_reference("Fix crash", "fix/123-null-price").as_deref(), Some("#123"));
+    assert_eq!(issue_reference("Fix crash", "feature/9-x").as_deref(), Some("#9"));
+  }
+
+  #[test]
+  fn issue_reference_ignores_versions_and_years_in_branches() {
+    for branch in ["upgrade-node-18", "release-2024", "release/2024", "fix/node-18", "123-fix"] {
+      assert_eq!(issue_reference("Bump things", branch), None, "{branch}");
+    }
+    assert_eq!(issue_reference("Price # sign", "main"), None);
+  }
+
+  #[test]
+  fn only_explicit_links_close_issues() {
+    let closes = IssueLink::Closes("#7".to_string());
+    let refs = IssueLink::Refs("#8".to_string());
+    assert!(build("Title", "", &[], Some(&closes)).body.ends_with("Closes #7"));
+    assert!(build("Title", "", &[], Some(&refs)).body.ends_with("Refs #8"));
+  }
+
+  #[test]
+  fn fill_template_puts_sections_under_matching_headings() {
+    let template = "## What does this do?\n\n## Testing\n\n## Related issues\n";
+    assert_eq!(
+      fill_template(template, &sections()),
+      "## What does this do?\n\nGuard against empty quotes.\n\n## Testing\n\n- test: passed \
+       (1.0s)\n\n## Related issues\n\nCloses #7\n\n```\n src/quote.rs | 4 ++--\n```\n",
+    );
+  }
+
+  #[test]
+  fn fill_template_uses_each_section_once() {
+    let template = "# Summary\n# Description\n";
+    let filled = fill_template(template, &Sections { changes: String::new(), ..sections() });
+    assert_eq!(filled.matches("Guard against empty quotes.").count(), 1);
+    assert!(filled.starts_with("# Summary\n\nGuard against empty quotes.\n# Description\n"));
+  }
+}
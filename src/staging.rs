@@ -0,0 +1,159 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::config;
+use crate::git;
+
+struct Entry {
+  path: String,
+  staged: bool,
+  unstaged: bool,
+}
+
+/// Runs git from the top of the work tree, where `git status` paths are relative to, against a
+/// private copy of the index.
+struct Index {
+  top: PathBuf,
+  scratch: PathBuf,
+}
+
+/// Let the user choose which files and hunks go into the commit.
+///
+/// Everything starts staged, as with `git add .`, so stray files can be toggled back out.
+/// Returns once the user confirms a non-empty index. Until then the choices are made on a copy
+/// of the index, so aborting, even with Ctrl-C, leaves the real one as it was.
+pub fn select() -> Result<()> {
+  let top = PathBuf::from(git::stdout(&["rev-parse", "--show-toplevel"])?.trim());
+  let real = top.join(git::stdout_in(&top, &["rev-parse", "--git-path", "index"])?.trim());
+  let dir = git::mrt_dir_in(&top)?;
+  fs::create_dir_all(&dir)?;
+
+  let index = Index { top, scratch: dir.join("staging-index") };
+  let _ = fs::copy(&real, &index.scratch);
+
+  let exclude = config::local_file_exclude();
+  let chosen = index.git(&["add", "--", ".", &exclude]).and_then(|()| index.choose());
+  match chosen {
+    Ok(()) => fs::rename(&index.scratch, &real)
+      .with_context(|| format!("failed to update {}", real.display())),
+    Err(e) => {
+      let _ = fs::remove_file(&index.scratch);
+      Err(e)
+    },
+  }
+}
+
+impl Index {
+  fn choose(&self) -> Result<()> {
+    loop {
+      let entries = self.entries()?;
+
+      println!();
+      for (i, entry) in entries.iter().enumerate() {
+        let mark = match (entry.staged, entry.unstaged) {
+          (true, false) => "x",
+          (true, true) => "~",
+          _ => " ",
+        };
+        println!("  {:>2} [{mark}] {}", i + 1, entry.path);
+      }
+      println!(
+        "\n<n>... toggle files, p <n> stage hunks, u <n> unstage hunks, d show staged diff, q \
+         abort, Enter to commit"
+      );
+      print!("> ");
+      std::io::stdout().flush()?;
+
+      let mut input = String::new();
+      if std::io::stdin().read_line(&mut input)? == 0 {
+        anyhow::bail!("ship aborted: end of input");
+      }
+      let words: Vec<&str> = input.split_whitespace().collect();
+
+      match words.as_slice() {
+        [] if entries.iter().any(|e| e.staged) => return Ok(()),
+        [] => println!("nothing staged"),
+        ["q"] => anyhow::bail!("ship aborted"),
+        ["d"] => self.git(&["diff", "--cached"])?,
+        ["p", n] => match pick(&entries, n) {
+          Some(entry) => self.git(&["add", "-p", "--", &entry.path])?,
+          None => println!("no file {n}"),
+        },
+        ["u", n] => match pick(&entries, n) {
+          Some(entry) => self.git(&["reset", "-p", "--", &entry.path])?,
+          None => println!("no file {n}"),
+        },
+        numbers => {
+          for n in numbers {
+            match pick(&entries, n) {
+              Some(entry) => self.toggle(entry)?,
+              None => println!("no file {n}"),
+            }
+          }
+        },
+      }
+    }
+  }
+
+  /// Fully staged files are taken out of the index; anything else is staged whole.
+  fn toggle(&self, entry: &Entry) -> Result<()> {
+    match entry.staged && !entry.unstaged {
+      true => self.git(&["reset", "-q", "--", &entry.path]),
+      false => self.git(&["add", "-A", "--", &entry.path]),
+    }
+  }
+
+  /// Changed files with their index state, from `git status --porcelain`.
+  fn entries(&self) -> Result<Vec<Entry>> {
+    let output = Command::new("git")
+      .args(["status", "--porcelain", "-z", "--no-renames", "--untracked-files=all", "--", "."])
+      .arg(config::local_file_exclude())
+      .current_dir(&self.top)
+      .env("GIT_INDEX_FILE", &self.scratch)
+      .output()
+      .context("failed to run git status")?;
+
+    if !output.status.success() {
+      anyhow::bail!("git status failed");
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let entries = text
+      .split('\0')
+      .filter(|record| record.len() > 3)
+      .map(|record| {
+        let (xy, path) = record.split_at(3);
+        let mut xy = xy.chars();
+        let (x, y) = (xy.next().unwrap_or(' '), xy.next().unwrap_or(' '));
+        Entry { path: path.to_string(), staged: !matches!(x, ' ' | '?'), unstaged: y != ' ' }
+      })
+      .collect();
+
+    Ok(entries)
+  }
+
+  fn git(&self, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+      .args(args)
+      .current_dir(&self.top)
+      .env("GIT_INDEX_FILE", &self.scratch)
+      .status()
+      .with_context(|| format!("failed to run git {}", args[0]))?;
+
+    if !status.success() {
+      anyhow::bail!("git {} failed", args.join(" "));
+    }
+
+    Ok(())
+  }
+}
+
+fn pick<'a>(entries: &'a [Entry], n: &str) -> Option<&'a Entry> {
+  let index = n.parse::<usize>().ok()?.checked_sub(1)?;
+  entries.get(index)
+}
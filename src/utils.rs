@@ -1,3 +1,39 @@
+use std::io::Write;
+
+use anyhow::Result;
+
 pub fn clear_screen() {
   print!("\x1B[2J\x1B[H");
 }
+
+/// Ask a yes/no question on the terminal, defaulting to no.
+pub fn confirm(question: &str) -> Result<bool> {
+  print!("{question} [y/N] ");
+  std::io::stdout().flush()?;
+
+  let mut input = String::new();
+  std::io::stdin().read_line(&mut input)?;
+  Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
+
+/// Ask the user to pick one of `options` by number. `None` if they enter nothing.
+pub fn choose(options: &[String]) -> Result<Option<usize>> {
+  for (i, option) in options.iter().enumerate() {
+    println!("  {:>2}  {option}", i + 1);
+  }
+
+  loop {
+    print!("> ");
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    match input.trim() {
+      "" => return Ok(None),
+      n => match n.parse::<usize>() {
+        Ok(n) if (1..=options.len()).contains(&n) => return Ok(Some(n - 1)),
+        _ => println!("pick a number from 1 to {}", options.len()),
+      },
+    }
+  }
+}
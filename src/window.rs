@@ -99,7 +99,7 @@ fn window_id_by_title(title: &str) -> Result<String> {
 
 /// Place a window at the given work-area-relative rect, compensating for CSD frame extents.
 fn place_window(
-  selector: &str, wid: &str, wa: &WorkArea, wa_x: i32, wa_y: i32, wa_w: i32, wa_h: i32,
+  wid: &str, wa: &WorkArea, wa_x: i32, wa_y: i32, wa_w: i32, wa_h: i32,
 ) -> Result<()> {
   let f = frame_extents(wid);
 
@@ -112,11 +112,11 @@ fn place_window(
 
   // Remove any maximized state first so wmctrl -e works
   let _ = Command::new("wmctrl")
-    .args(["-r", selector, "-b", "remove,maximized_vert,maximized_horz"])
+    .args(["-i", "-r", wid, "-b", "remove,maximized_vert,maximized_horz"])
     .status();
 
   Command::new("wmctrl")
-    .args(["-r", selector, "-e", &format!("0,{x},{y},{w},{h}")])
+    .args(["-i", "-r", wid, "-e", &format!("0,{x},{y},{w},{h}")])
     .status()
     .context("failed to run wmctrl -e")?;
 
@@ -128,13 +128,13 @@ pub fn snap_active_right() -> Result<()> {
   let wa = work_area()?;
   let wid = active_window_id()?;
   let half = wa.w / 2;
-  place_window(":ACTIVE:", &wid, &wa, half, 0, wa.w - half, wa.h)
+  place_window(&wid, &wa, half, 0, wa.w - half, wa.h)
 }
 
-/// Snap a window (found by title substring) to the left half of the screen.
+/// Snap a window (found by a regex on its title) to the left half of the screen.
 pub fn snap_window_left(title: &str) -> Result<()> {
   let wa = work_area()?;
   let wid = window_id_by_title(title)?;
   let half = wa.w / 2;
-  place_window(title, &wid, &wa, 0, 0, half, wa.h)
+  place_window(&wid, &wa, 0, 0, half, wa.h)
 }
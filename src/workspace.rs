@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+use crate::config::MrtConfig;
+
+/// Overrides the `projects` config key with a `:`-separated list of roots.
+const PROJECTS_ENV: &str = "MRT_PROJECTS";
+
+pub fn home() -> Result<PathBuf> {
+  match std::env::var_os("HOME") {
+    Some(home) if !home.is_empty() => Ok(PathBuf::from(home)),
+    _ => anyhow::bail!("HOME is not set; set it or use absolute paths for the projects roots"),
+  }
+}
+
+/// The directories repos live under, in search order, from `MRT_PROJECTS` or the `projects`
+/// config key. A leading `~` is expanded.
+pub fn roots(config: &MrtConfig) -> Result<Vec<PathBuf>> {
+  let configured: Vec<String> = match std::env::var(PROJECTS_ENV) {
+    Ok(env) => env.split(':').filter(|r| !r.is_empty()).map(str::to_string).collect(),
+    Err(_) => config.projects.clone(),
+  };
+
+  if configured.is_empty() {
+    anyhow::bail!("no projects roots configured; set `projects` in the config or {PROJECTS_ENV}");
+  }
+
+  configured.iter().map(|root| expand(root)).collect()
+}
+
+/// The first root, where new repos and crates are created.
+pub fn primary_root(config: &MrtConfig) -> Result<PathBuf> {
+  Ok(roots(config)?.remove(0))
+}
+
+/// The repo called `name` under the first root that has it.
+pub fn find_repo(config: &MrtConfig, name: &str) -> Result<PathBuf> {
+  let roots = roots(config)?;
+  if let Some(dir) = roots.iter().map(|root| root.join(name)).find(|dir| dir.exists()) {
+    return Ok(dir);
+  }
+
+  let searched: Vec<String> = roots.iter().map(|r| r.display().to_string()).collect();
+  anyhow::bail!("repository not found: {name} (searched {})", searched.join(", "))
+}
+
+fn expand(root: &str) -> Result<PathBuf> {
+  match root.strip_prefix('~') {
+    Some(rest) => Ok(home()?.join(rest.trim_start_matches('/'))),
+    None => Ok(PathBuf::from(root)),
+  }
+}
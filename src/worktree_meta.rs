@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::git;
+
+/// What `mrt fix` knew when it made a worktree, kept in the worktree's own git dir so commands
+/// run inside it later, such as `mrt ship`, can pick it up.
+#[derive(Serialize, Deserialize, Default)]
+pub struct WorktreeMeta {
+  /// The issue the fix is for, as `#123`
+  #[serde(default)]
+  pub issue: Option<String>,
+  #[serde(default)]
+  pub issue_url: Option<String>,
+  /// The branch on origin the fix's PR targets, when `--from` started it somewhere other than
+  /// the default branch
+  #[serde(default)]
+  pub base: Option<String>,
+}
+
+impl WorktreeMeta {
+  /// The metadata of the worktree containing the current directory, if mrt made it.
+  pub fn load() -> Result<Option<Self>> {
+    Self::load_in(Path::new("."))
+  }
+
+  pub fn load_in(worktree: &Path) -> Result<Option<Self>> {
+    let path = meta_path(worktree)?;
+    let Ok(contents) = fs::read_to_string(&path) else {
+      return Ok(None);
+    };
+
+    toml::from_str(&contents)
+      .map(Some)
+      .with_context(|| format!("failed to parse {}", path.display()))
+  }
+
+  pub fn save_in(&self, worktree: &Path) -> Result<()> {
+    let path = meta_path(worktree)?;
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let contents = toml::to_string(self).context("failed to serialize worktree metadata")?;
+    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))
+  }
+}
+
+/// `<worktree git dir>/mrt/fix.toml`.
+fn meta_path(worktree: &Path) -> Result<PathBuf> {
+  Ok(git::mrt_dir_in(worktree)?.join("fix.toml"))
+}
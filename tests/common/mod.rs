@@ -0,0 +1,138 @@
+//! A sandbox for running `mrt ship` against a stub `gh` on PATH and a local bare origin.
+
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Output;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Answers the `gh` calls ship and the watcher make, logging each to `bin/calls`. `pr create`
+/// leaves a marker so `pr view` only finds the PR once it exists, and fails once after creating
+/// it if `bin/create-fails` exists; everything else is steered by environment variables. With
+/// `NOT_REQUIRED` no check is required, and `LINT_BUCKET` adds a `lint` check.
+const STUB_GH: &str = r#"#!/bin/sh
+dir=$(dirname "$0")
+echo "$*" >> "$dir/calls"
+case "$1 $2" in
+  "pr create")
+    touch "$dir/pr"
+    [ ! -f "$dir/create-fails" ] || { rm "$dir/create-fails"; echo "timed out" >&2; exit 1; }
+    echo "https://github.com/o/r/pull/7" ;;
+  "pr view")
+    [ -f "$dir/pr" ] || { echo "no pull requests found" >&2; exit 1; }
+    echo "{\"url\":\"https://github.com/o/r/pull/7\",\"state\":\"$PR_STATE\",\"mergeStateStatus\":\"CLEAN\"}" ;;
+  "pr merge") [ -z "$MERGE_FAILS" ] || { echo "auto-merge is not allowed" >&2; exit 1; } ;;
+  "pr checks")
+    runs="{\"name\":\"test\",\"bucket\":\"$TEST_BUCKET\",\"link\":\"https://github.com/o/r/actions/runs/1/job/99\"}"
+    case "$*" in
+      *--required*) [ -z "$NOT_REQUIRED" ] || runs="" ;;
+      *) [ -z "$LINT_BUCKET" ] || runs="$runs,{\"name\":\"lint\",\"bucket\":\"$LINT_BUCKET\",\"link\":\"https://github.com/o/r/actions/runs/1/job/98\"}" ;;
+    esac
+    echo "[$runs]"
+    [ "$TEST_BUCKET" = pass ] && [ "${LINT_BUCKET:-pass}" = pass ] || exit 8 ;;
+  "run view") seq 1 100 | sed 's/^/log line /' ;;
+esac
+"#;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct Sandbox {
+  pub root: PathBuf,
+}
+
+impl Sandbox {
+  /// A repo on branch `feat` with an uncommitted change, pushing to a bare origin.
+  pub fn new(name: &str) -> Self {
+    let root = std::env::temp_dir().join(format!("mrt-ship-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("bin")).unwrap();
+    fs::create_dir_all(root.join("home/.config/mrt")).unwrap();
+
+    let gh = root.join("bin/gh");
+    fs::write(&gh, STUB_GH).unwrap();
+    fs::set_permissions(&gh, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(root.join("home/.config/mrt/config.toml"), "[ship]\nforge = \"github\"\n").unwrap();
+
+    let sandbox = Sandbox { root };
+    sandbox.git(&["init", "-q", "--bare", "-b", "main", "origin.git"], &sandbox.root);
+    sandbox.git(&["init", "-q", "-b", "main", "repo"], &sandbox.root);
+
+    let repo = sandbox.repo();
+    fs::write(repo.join("a"), "a\n").unwrap();
+    sandbox.git(&["remote", "add", "origin", "../origin.git"], &repo);
+    sandbox.git(&["add", "a"], &repo);
+    sandbox.git(&["commit", "-qm", "init"], &repo);
+    sandbox.git(&["push", "-q", "origin", "main"], &repo);
+    sandbox.git(&["checkout", "-qb", "feat"], &repo);
+    fs::write(repo.join("a"), "a\nb\n").unwrap();
+    sandbox
+  }
+
+  pub fn repo(&self) -> PathBuf {
+    self.root.join("repo")
+  }
+
+  /// Run git in `dir` and return its trimmed stdout.
+  pub fn git(&self, args: &[&str], dir: &Path) -> String {
+    let output = Command::new("git")
+      .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+      .args(args)
+      .current_dir(dir)
+      .env("HOME", self.root.join("home"))
+      .output()
+      .unwrap();
+    assert!(output.status.success(), "git {args:?} failed: {}", text(&output.stderr));
+    text(&output.stdout).trim().to_string()
+  }
+
+  /// Run `mrt ship` with the stub on PATH, confirming its review prompts, and give up if it
+  /// doesn't finish in time.
+  pub fn ship(&self, args: &[&str], env: &[(&str, &str)]) -> Output {
+    let path = format!("{}:{}", self.root.join("bin").display(), std::env::var("PATH").unwrap());
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mrt"))
+      .arg("ship")
+      .args(args)
+      .current_dir(self.repo())
+      .env("PATH", path)
+      .env("HOME", self.root.join("home"))
+      .env_remove("XDG_CONFIG_HOME")
+      .env("GIT_AUTHOR_NAME", "t")
+      .env("GIT_AUTHOR_EMAIL", "t@t")
+      .env("GIT_COMMITTER_NAME", "t")
+      .env("GIT_COMMITTER_EMAIL", "t@t")
+      .envs(env.iter().copied())
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .unwrap();
+
+    child.stdin.take().unwrap().write_all(b"\n\n").unwrap();
+
+    let deadline = Instant::now() + TIMEOUT;
+    while child.try_wait().unwrap().is_none() {
+      if Instant::now() > deadline {
+        let _ = child.kill();
+        panic!("mrt ship {args:?} was still running after {TIMEOUT:?}");
+      }
+      thread::sleep(Duration::from_millis(100));
+    }
+    child.wait_with_output().unwrap()
+  }
+}
+
+impl Drop for Sandbox {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.root);
+  }
+}
+
+pub fn text(bytes: &[u8]) -> String {
+  String::from_utf8_lossy(bytes).into_owned()
+}
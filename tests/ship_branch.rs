@@ -0,0 +1,50 @@
+//! `mrt ship` moving work off the base branch onto a feature branch.
+
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+use common::text;
+
+#[test]
+fn shipping_from_the_base_moves_local_commits_onto_the_new_branch() {
+  let sandbox = Sandbox::new("branch-base");
+  let repo = sandbox.repo();
+  sandbox.git(&["checkout", "-q", "main"], &repo);
+  sandbox.git(&["commit", "-qam", "Local work"], &repo);
+  fs::write(repo.join("c"), "c\n").unwrap();
+
+  let output = sandbox
+    .ship(&["Add c", "--all", "--branch", "topic", "--no-auto-merge"], &[("PR_STATE", "OPEN")]);
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert!(text(&output.stdout).contains("moved 1 local commit(s) from main onto topic"));
+  assert_eq!(sandbox.git(&["branch", "--show-current"], &repo), "topic");
+  assert_eq!(
+    sandbox.git(&["rev-parse", "main"], &repo),
+    sandbox.git(&["rev-parse", "origin/main"], &repo)
+  );
+  assert_eq!(
+    sandbox.git(&["log", "--format=%s", "origin/main..topic"], &repo),
+    "Add c\nLocal work"
+  );
+  assert_eq!(
+    sandbox.git(&["rev-parse", "topic"], &repo),
+    sandbox.git(&["rev-parse", "origin/topic"], &repo)
+  );
+}
+
+#[test]
+fn branch_flag_is_refused_on_a_feature_branch() {
+  let sandbox = Sandbox::new("branch-feature");
+  let repo = sandbox.repo();
+
+  let output = sandbox
+    .ship(&["Add b", "--all", "--branch", "topic", "--no-auto-merge"], &[("PR_STATE", "OPEN")]);
+
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("already on feat"));
+  assert_eq!(sandbox.git(&["branch", "--show-current"], &repo), "feat");
+  assert_eq!(sandbox.git(&["rev-list", "--count", "main..feat"], &repo), "0");
+}
@@ -0,0 +1,100 @@
+//! Interrupted `mrt ship` runs picked up again with `--resume`.
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+
+use common::Sandbox;
+use common::text;
+
+/// A pre-push hook that rejects the next push only.
+const FAIL_ONCE_HOOK: &str = r#"#!/bin/sh
+marker="$(git rev-parse --git-dir)/fail-push"
+[ ! -f "$marker" ] || { rm "$marker"; echo "remote hung up" >&2; exit 1; }
+"#;
+
+/// Make the sandbox's next `git push` fail.
+fn fail_next_push(sandbox: &Sandbox) {
+  let hooks = sandbox.repo().join(".git/hooks");
+  fs::create_dir_all(&hooks).unwrap();
+  fs::write(hooks.join("pre-push"), FAIL_ONCE_HOOK).unwrap();
+  fs::set_permissions(hooks.join("pre-push"), fs::Permissions::from_mode(0o755)).unwrap();
+  fs::write(sandbox.repo().join(".git/fail-push"), "").unwrap();
+}
+
+/// A check that counts its runs in `check-runs` under the sandbox root.
+fn count_check_runs(sandbox: &Sandbox) {
+  let runs = sandbox.root.join("check-runs");
+  let config = format!(
+    "[ship]\nforge = \"github\"\n\n[[checks]]\nname = \"count\"\ncommand = \"echo run >> {}\"\n",
+    runs.display()
+  );
+  fs::write(sandbox.root.join("home/.config/mrt/config.toml"), config).unwrap();
+}
+
+fn gh_calls(sandbox: &Sandbox, call: &str) -> usize {
+  let calls = fs::read_to_string(sandbox.root.join("bin/calls")).unwrap_or_default();
+  calls.lines().filter(|line| line.starts_with(call)).count()
+}
+
+fn commits_on_feat(sandbox: &Sandbox) -> String {
+  sandbox.git(&["rev-list", "--count", "main..feat"], &sandbox.repo())
+}
+
+#[test]
+fn resume_after_a_failed_push_finishes_without_repeating_steps() {
+  let sandbox = Sandbox::new("resume-push");
+  count_check_runs(&sandbox);
+  fail_next_push(&sandbox);
+  let env = [("PR_STATE", "OPEN")];
+
+  let output = sandbox.ship(&["Add b", "--all", "--no-auto-merge"], &env);
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("git push failed"));
+  assert_eq!(commits_on_feat(&sandbox), "1");
+
+  let output = sandbox.ship(&["--resume", "--no-cache", "--no-auto-merge"], &env);
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert_eq!(commits_on_feat(&sandbox), "1");
+  let pushed = sandbox.git(&["rev-parse", "origin/feat"], &sandbox.repo());
+  assert_eq!(pushed, sandbox.git(&["rev-parse", "feat"], &sandbox.repo()));
+  assert_eq!(gh_calls(&sandbox, "pr create"), 1);
+  assert_eq!(fs::read_to_string(sandbox.root.join("check-runs")).unwrap(), "run\n");
+}
+
+#[test]
+fn new_ship_is_refused_while_a_committed_one_is_unfinished() {
+  let sandbox = Sandbox::new("resume-refuse");
+  fail_next_push(&sandbox);
+  let env = [("PR_STATE", "OPEN")];
+
+  let output = sandbox.ship(&["Add b", "--all", "--no-auto-merge"], &env);
+  assert!(!output.status.success());
+
+  fs::write(sandbox.repo().join("c"), "c\n").unwrap();
+  let output = sandbox.ship(&["Add c", "--all", "--no-auto-merge"], &env);
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("a previous ship already committed \"Add b\""));
+  assert_eq!(commits_on_feat(&sandbox), "1");
+  assert_eq!(gh_calls(&sandbox, "pr create"), 0);
+}
+
+#[test]
+fn resume_uses_the_pr_an_interrupted_ship_opened() {
+  let sandbox = Sandbox::new("resume-pr");
+  fs::write(sandbox.root.join("bin/create-fails"), "").unwrap();
+  let env = [("PR_STATE", "OPEN")];
+
+  let output = sandbox.ship(&["Add b", "--all", "--no-auto-merge"], &env);
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("timed out"));
+
+  let output = sandbox.ship(&["--resume", "--no-auto-merge"], &env);
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  let stdout = text(&output.stdout);
+  assert!(stdout.contains("using existing PR"));
+  assert!(stdout.contains("https://github.com/o/r/pull/7"));
+  assert_eq!(gh_calls(&sandbox, "pr create"), 1);
+  assert_eq!(commits_on_feat(&sandbox), "1");
+}
@@ -0,0 +1,56 @@
+//! What `mrt ship` stages, and what it leaves out.
+
+mod common;
+
+use std::fs;
+
+use common::Sandbox;
+use common::text;
+
+/// A sandbox with personal overrides beside the repo config and in a subdirectory.
+fn with_local_config(name: &str) -> Sandbox {
+  let sandbox = Sandbox::new(name);
+  let repo = sandbox.repo();
+  fs::create_dir_all(repo.join("sub")).unwrap();
+  fs::write(repo.join(".mrt.local.toml"), "[ship]\ndraft = true\n").unwrap();
+  fs::write(repo.join("sub/.mrt.local.toml"), "[ship]\ndraft = true\n").unwrap();
+  fs::write(repo.join("sub/c"), "c\n").unwrap();
+  sandbox
+}
+
+fn committed(sandbox: &Sandbox) -> String {
+  sandbox.git(&["show", "--name-only", "--format=", "HEAD"], &sandbox.repo())
+}
+
+#[test]
+fn ship_all_leaves_local_config_uncommitted() {
+  let sandbox = with_local_config("staging-all");
+
+  let output = sandbox.ship(&["Add b", "--all", "--no-auto-merge"], &[("PR_STATE", "OPEN")]);
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert_eq!(committed(&sandbox), "a\nsub/c");
+}
+
+#[test]
+fn interactive_staging_leaves_local_config_uncommitted() {
+  let sandbox = with_local_config("staging-select");
+
+  let output = sandbox.ship(&["Add b", "--no-auto-merge"], &[("PR_STATE", "OPEN")]);
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert!(!text(&output.stdout).contains(".mrt.local.toml"));
+  assert_eq!(committed(&sandbox), "a\nsub/c");
+}
+
+#[test]
+fn dry_run_plan_leaves_local_config_out() {
+  let sandbox = with_local_config("staging-dry-run");
+
+  let output = sandbox.ship(&["Add b", "--dry-run"], &[]);
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  let stdout = text(&output.stdout);
+  assert!(stdout.contains("add 'sub/c'"));
+  assert!(!stdout.contains(".mrt.local.toml"));
+}
@@ -0,0 +1,74 @@
+//! `mrt ship --watch` against a stub `gh` on PATH and a local bare origin.
+
+mod common;
+
+use common::Sandbox;
+use common::text;
+
+#[test]
+fn watch_succeeds_once_checks_pass() {
+  let sandbox = Sandbox::new("pass");
+  let output = sandbox.ship(
+    &["Add b", "--all", "--watch", "--no-auto-merge"],
+    &[("PR_STATE", "OPEN"), ("TEST_BUCKET", "pass")],
+  );
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert!(text(&output.stdout).contains("all checks passed"));
+}
+
+#[test]
+fn watch_fails_with_the_log_tail_of_a_required_check() {
+  let sandbox = Sandbox::new("fail");
+  let output =
+    sandbox.ship(&["Add b", "--all", "--watch"], &[("PR_STATE", "OPEN"), ("TEST_BUCKET", "fail")]);
+
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("required check 'test' failed"));
+  let stdout = text(&output.stdout);
+  assert!(stdout.contains("log line 100"));
+  assert!(!stdout.contains("log line 60\n"));
+}
+
+#[test]
+fn watch_succeeds_once_merged() {
+  let sandbox = Sandbox::new("merged");
+  let output = sandbox.ship(
+    &["Add b", "--all", "--watch", "--auto-merge"],
+    &[("PR_STATE", "MERGED"), ("TEST_BUCKET", "pass")],
+  );
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert!(text(&output.stdout).contains("\nmerged"));
+}
+
+#[test]
+fn watch_stops_at_green_checks_when_auto_merge_could_not_be_enabled() {
+  let sandbox = Sandbox::new("no-auto-merge");
+  let output = sandbox.ship(
+    &["Add b", "--all", "--watch", "--auto-merge"],
+    &[("PR_STATE", "OPEN"), ("TEST_BUCKET", "pass"), ("MERGE_FAILS", "1")],
+  );
+
+  assert!(output.status.success(), "{}", text(&output.stderr));
+  assert!(text(&output.stderr).contains("could not enable auto-merge"));
+  assert!(text(&output.stdout).contains("all checks passed"));
+}
+
+#[test]
+fn watch_without_auto_merge_fails_once_checks_settle_with_an_optional_failure() {
+  let sandbox = Sandbox::new("optional-fail");
+  let output = sandbox.ship(
+    &["Add b", "--all", "--watch", "--no-auto-merge"],
+    &[
+      ("PR_STATE", "OPEN"),
+      ("TEST_BUCKET", "pass"),
+      ("LINT_BUCKET", "fail"),
+      ("NOT_REQUIRED", "1"),
+    ],
+  );
+
+  assert!(!output.status.success());
+  assert!(text(&output.stderr).contains("check 'lint' failed"));
+  assert!(text(&output.stdout).contains("log line 100"));
+}